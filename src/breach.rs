@@ -0,0 +1,103 @@
+use sha1::{Digest, Sha1};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+
+/// Below this many bytes the remaining range is scanned line by line.
+const LINEAR_SCAN_BYTES: u64 = 4096;
+
+/// Looks a password up in a locally downloaded Have I Been Pwned "pwned
+/// passwords" file, returning how many times it appeared in breaches (0 if
+/// never). The password never leaves this machine.
+///
+/// The file must be the SHA-1 edition ordered by hash, with one
+/// `HASH:COUNT` line per entry, so it can be binary searched without loading
+/// it into memory.
+pub fn breach_count(db: &Path, password: &str) -> Result<u64> {
+    let target = hex::encode_upper(Sha1::digest(password.as_bytes()));
+    let file = File::open(db).map_err(|e| {
+        Error::Breach(format!(
+            "Failed to open breach database {}: {}",
+            db.display(),
+            e
+        ))
+    })?;
+    let mut reader = BufReader::new(file);
+    let mut lo = 0;
+    let mut hi = reader.get_ref().metadata()?.len();
+
+    // Invariant: `lo` is the start of a line, and if the target is present
+    // its line starts somewhere in lo..hi.
+    while hi - lo > LINEAR_SCAN_BYTES {
+        let mid = lo + (hi - lo) / 2;
+        let start = next_line_start(&mut reader, mid)?;
+        if start >= hi {
+            hi = mid;
+            continue;
+        }
+
+        let (line_len, hash, count) = read_entry(&mut reader)?;
+        match hash.as_str().cmp(target.as_str()) {
+            std::cmp::Ordering::Less => lo = start + line_len,
+            std::cmp::Ordering::Equal => return Ok(count),
+            std::cmp::Ordering::Greater => hi = start,
+        }
+    }
+
+    reader.seek(SeekFrom::Start(lo))?;
+    let mut position = lo;
+    while position < hi {
+        let (line_len, hash, count) = read_entry(&mut reader)?;
+        if line_len == 0 {
+            break;
+        }
+        match hash.as_str().cmp(target.as_str()) {
+            std::cmp::Ordering::Less => position += line_len,
+            std::cmp::Ordering::Equal => return Ok(count),
+            std::cmp::Ordering::Greater => break,
+        }
+    }
+
+    Ok(0)
+}
+
+/// Positions the reader at the first line starting at or after `offset` and
+/// returns that position.
+fn next_line_start(reader: &mut BufReader<File>, offset: u64) -> Result<u64> {
+    if offset == 0 {
+        reader.seek(SeekFrom::Start(0))?;
+        return Ok(0);
+    }
+    reader.seek(SeekFrom::Start(offset - 1))?;
+    let mut skipped = Vec::new();
+    let skipped_len = reader.read_until(b'\n', &mut skipped)? as u64;
+    Ok(offset - 1 + skipped_len)
+}
+
+/// Reads one `HASH:COUNT` line, returning its length in bytes, the
+/// upper-cased hash and the count. Returns a zero length at end of file.
+fn read_entry(reader: &mut BufReader<File>) -> Result<(u64, String, u64)> {
+    let mut line = String::new();
+    let line_len = reader.read_line(&mut line)? as u64;
+    if line_len == 0 {
+        return Ok((0, String::new(), 0));
+    }
+
+    let (hash, count) = line
+        .trim_end()
+        .split_once(':')
+        .filter(|(hash, _)| hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        .ok_or_else(|| {
+            Error::Breach(
+                "Breach database is not a SHA-1 pwned passwords file ordered by hash".to_string(),
+            )
+        })?;
+    let count = count
+        .trim()
+        .parse()
+        .map_err(|_| Error::Breach(format!("Malformed breach count in database: {}", count)))?;
+
+    Ok((line_len, hash.to_uppercase(), count))
+}
@@ -0,0 +1,278 @@
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use passgen::secret::SecretString;
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+use zeroize::Zeroizing;
+
+/// A way of reaching the system clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardBackend {
+    /// macOS `pbcopy`/`pbpaste`.
+    Pbcopy,
+    /// Wayland `wl-copy`/`wl-paste`.
+    WlCopy,
+    /// X11 `xclip`.
+    Xclip,
+    /// X11 `xsel`.
+    Xsel,
+    /// OSC 52 terminal escape sequence. Works over SSH and in most modern
+    /// terminals, but the clipboard cannot be read back.
+    Osc52,
+}
+
+impl ClipboardBackend {
+    pub const ALL: [ClipboardBackend; 5] = [
+        ClipboardBackend::Pbcopy,
+        ClipboardBackend::WlCopy,
+        ClipboardBackend::Xclip,
+        ClipboardBackend::Xsel,
+        ClipboardBackend::Osc52,
+    ];
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|backend| backend.name() == name.to_lowercase())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown clipboard backend: {} (valid: {})",
+                    name,
+                    Self::ALL
+                        .iter()
+                        .map(|backend| backend.name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ClipboardBackend::Pbcopy => "pbcopy",
+            ClipboardBackend::WlCopy => "wl-copy",
+            ClipboardBackend::Xclip => "xclip",
+            ClipboardBackend::Xsel => "xsel",
+            ClipboardBackend::Osc52 => "osc52",
+        }
+    }
+
+    /// Picks a backend for the current session: `$PASSGEN_CLIPBOARD` if set,
+    /// otherwise pbcopy on macOS, then Wayland, then X11, then OSC 52 when
+    /// attached to a terminal.
+    pub fn detect() -> Result<Self> {
+        if let Ok(name) = std::env::var("PASSGEN_CLIPBOARD") {
+            if !name.is_empty() {
+                return Self::from_name(&name);
+            }
+        }
+
+        if cfg!(target_os = "macos") && on_path("pbcopy") {
+            return Ok(ClipboardBackend::Pbcopy);
+        }
+        if env_set("WAYLAND_DISPLAY") && on_path("wl-copy") {
+            return Ok(ClipboardBackend::WlCopy);
+        }
+        if env_set("DISPLAY") {
+            if on_path("xclip") {
+                return Ok(ClipboardBackend::Xclip);
+            }
+            if on_path("xsel") {
+                return Ok(ClipboardBackend::Xsel);
+            }
+        }
+        if std::io::stdout().is_terminal() || std::fs::File::open("/dev/tty").is_ok() {
+            return Ok(ClipboardBackend::Osc52);
+        }
+
+        Err(anyhow::anyhow!(
+            "No clipboard available: install wl-clipboard, xclip or xsel, or run in a terminal that supports OSC 52"
+        ))
+    }
+
+    /// Replaces the clipboard contents with `text`.
+    pub fn copy(self, text: &str) -> Result<()> {
+        let (program, args): (&str, &[&str]) = match self {
+            ClipboardBackend::Pbcopy => ("pbcopy", &[]),
+            ClipboardBackend::WlCopy => ("wl-copy", &["--type", "text/plain"]),
+            ClipboardBackend::Xclip => ("xclip", &["-selection", "clipboard", "-in"]),
+            ClipboardBackend::Xsel => ("xsel", &["--clipboard", "--input"]),
+            ClipboardBackend::Osc52 => return osc52_copy(text),
+        };
+
+        // wl-copy and xclip fork a process that keeps serving the selection,
+        // so their output must not be captured or waiting would never end.
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Failed to run {}: {}", program, e))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+
+        if !child.wait()?.success() {
+            return Err(anyhow::anyhow!(
+                "Failed to copy to clipboard with {}",
+                program
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Reads the clipboard, or `None` if this backend cannot read it.
+    pub fn paste(self) -> Result<Option<SecretString>> {
+        let (program, args): (&str, &[&str]) = match self {
+            ClipboardBackend::Pbcopy => ("pbpaste", &[]),
+            ClipboardBackend::WlCopy => ("wl-paste", &["--no-newline"]),
+            ClipboardBackend::Xclip => ("xclip", &["-selection", "clipboard", "-out"]),
+            ClipboardBackend::Xsel => ("xsel", &["--clipboard", "--output"]),
+            ClipboardBackend::Osc52 => return Ok(None),
+        };
+
+        let output = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .map_err(|e| anyhow::anyhow!("Failed to run {}: {}", program, e))?;
+
+        // An empty clipboard makes wl-paste and xclip exit non-zero.
+        if !output.status.success() {
+            return Ok(Some(SecretString::default()));
+        }
+        let stdout = Zeroizing::new(output.stdout);
+        Ok(Some(SecretString::new(
+            String::from_utf8_lossy(&stdout).into_owned(),
+        )))
+    }
+
+    /// Empties the clipboard.
+    pub fn clear(self) -> Result<()> {
+        match self {
+            ClipboardBackend::WlCopy => {
+                let status = Command::new("wl-copy")
+                    .arg("--clear")
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status()?;
+                if !status.success() {
+                    return Err(anyhow::anyhow!("Failed to clear clipboard with wl-copy"));
+                }
+                Ok(())
+            }
+            _ => self.copy(""),
+        }
+    }
+}
+
+/// What happened when a copied secret's timer ran out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClearOutcome {
+    /// The clipboard held the secret and its earlier contents were put back.
+    Restored,
+    /// The clipboard held the secret and was emptied.
+    Cleared,
+    /// Something else was copied in the meantime, so it was left alone.
+    Changed,
+    /// The backend cannot read the clipboard, so it was left alone.
+    Unverifiable,
+}
+
+/// A secret placed on the clipboard, remembering what was there before so
+/// it can be put back.
+pub struct CopiedSecret {
+    backend: ClipboardBackend,
+    secret: SecretString,
+    previous: Option<SecretString>,
+}
+
+impl CopiedSecret {
+    pub fn backend(&self) -> ClipboardBackend {
+        self.backend
+    }
+
+    /// Waits for `delay`, then restores the previous clipboard contents (or
+    /// clears it) if and only if it still holds the secret. Ctrl-C, SIGTERM
+    /// or SIGHUP during the wait does the same at once rather than leaving
+    /// the secret behind; the returned flag says whether that happened, so
+    /// the caller can exit as interrupted.
+    pub fn clear_after(self, delay: Duration) -> Result<(ClearOutcome, bool)> {
+        let (interrupt, interrupted) = mpsc::channel();
+        ctrlc::set_handler(move || {
+            let _ = interrupt.send(());
+        })
+        .map_err(|e| anyhow::anyhow!("Failed to install the Ctrl-C handler: {}", e))?;
+        let interrupted = interrupted.recv_timeout(delay).is_ok();
+
+        Ok((self.restore()?, interrupted))
+    }
+
+    fn restore(self) -> Result<ClearOutcome> {
+        match self.backend.paste()? {
+            None => Ok(ClearOutcome::Unverifiable),
+            Some(current) if current != self.secret => Ok(ClearOutcome::Changed),
+            Some(_) => match self.previous.filter(|previous| !previous.is_empty()) {
+                Some(previous) => {
+                    self.backend.copy(previous.expose())?;
+                    Ok(ClearOutcome::Restored)
+                }
+                None => {
+                    self.backend.clear()?;
+                    Ok(ClearOutcome::Cleared)
+                }
+            },
+        }
+    }
+}
+
+/// Copies `secret` with the detected backend. When `remember_previous` is
+/// set the current clipboard contents are read first so `clear_after` can
+/// restore them.
+pub fn copy_secret(secret: &str, remember_previous: bool) -> Result<CopiedSecret> {
+    let backend = ClipboardBackend::detect()?;
+    let previous = if remember_previous {
+        backend.paste().unwrap_or(None)
+    } else {
+        None
+    };
+    backend.copy(secret)?;
+
+    Ok(CopiedSecret {
+        backend,
+        secret: SecretString::new(secret.to_string()),
+        previous,
+    })
+}
+
+/// Sends an OSC 52 "set clipboard" escape to the controlling terminal.
+fn osc52_copy(text: &str) -> Result<()> {
+    let sequence = format!(
+        "\x1b]52;c;{}\x07",
+        general_purpose::STANDARD.encode(text.as_bytes())
+    );
+    match std::fs::OpenOptions::new().write(true).open("/dev/tty") {
+        Ok(mut tty) => tty.write_all(sequence.as_bytes())?,
+        Err(_) => {
+            let mut stdout = std::io::stdout();
+            stdout.write_all(sequence.as_bytes())?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn env_set(name: &str) -> bool {
+    std::env::var_os(name).is_some_and(|value| !value.is_empty())
+}
+
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
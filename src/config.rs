@@ -0,0 +1,114 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The config file: named profiles under `[profiles.<name>]`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+/// Saved generation settings. Every field is optional; options given on the
+/// command line or in a web request take precedence.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Profile {
+    pub length: Option<usize>,
+    pub uppercase: Option<bool>,
+    pub lowercase: Option<bool>,
+    pub numbers: Option<bool>,
+    pub special: Option<bool>,
+    pub charset: Option<Vec<String>>,
+    pub include_chars: Option<String>,
+    pub exclude_similar: Option<bool>,
+    pub exclude_ambiguous: Option<bool>,
+    pub require_each: Option<bool>,
+    pub min_uppercase: Option<usize>,
+    pub min_lowercase: Option<usize>,
+    pub min_numbers: Option<usize>,
+    pub min_special: Option<usize>,
+    pub max_uppercase: Option<usize>,
+    pub max_lowercase: Option<usize>,
+    pub max_numbers: Option<usize>,
+    pub max_special: Option<usize>,
+    pub rules: Option<String>,
+    pub format: Option<String>,
+    // Clipboard settings only mean something to the CLI.
+    #[allow(dead_code)]
+    pub copy: Option<bool>,
+    #[allow(dead_code)]
+    pub clear_after: Option<u64>,
+    pub words: Option<usize>,
+    pub separator: Option<String>,
+    pub wordlist: Option<String>,
+}
+
+impl Config {
+    /// `$PASSGEN_CONFIG`, or `passgen/config.toml` under `$XDG_CONFIG_HOME`
+    /// (default `~/.config`).
+    pub fn default_path() -> PathBuf {
+        if let Ok(path) = std::env::var("PASSGEN_CONFIG") {
+            return PathBuf::from(path);
+        }
+        let config_dir = match std::env::var("XDG_CONFIG_HOME") {
+            Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()))
+                .join(".config"),
+        };
+        config_dir.join("passgen").join("config.toml")
+    }
+
+    /// Loads the config at the default path; a missing file is an empty
+    /// config.
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::default_path())
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self {
+                    path: path.to_path_buf(),
+                    ..Self::default()
+                })
+            }
+            Err(e) => {
+                return Err(anyhow::anyhow!(
+                    "Failed to read config {}: {}",
+                    path.display(),
+                    e
+                ))
+            }
+        };
+
+        let mut config: Self = toml::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("Invalid config {}: {}", path.display(), e))?;
+        config.path = path.to_path_buf();
+        Ok(config)
+    }
+
+    pub fn profile(&self, name: &str) -> Result<&Profile> {
+        self.profiles.get(name).ok_or_else(|| {
+            if self.profiles.is_empty() {
+                anyhow::anyhow!(
+                    "Unknown profile: {} (no profiles defined in {})",
+                    name,
+                    self.path.display()
+                )
+            } else {
+                anyhow::anyhow!(
+                    "Unknown profile: {} (defined in {}: {})",
+                    name,
+                    self.path.display(),
+                    self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+                )
+            }
+        })
+    }
+}
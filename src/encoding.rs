@@ -0,0 +1,234 @@
+//! Encodings and quoting styles for `format_password`, so a secret can be
+//! pasted straight into a URL, shell command or config file.
+
+use base64::{engine::general_purpose, Engine as _};
+
+use crate::error::{Error, Result};
+
+/// Every format name `encode` accepts, with a short description.
+pub const FORMATS: &[(&str, &str)] = &[
+    ("plain", "the password as is"),
+    ("base64", "standard base64 with padding"),
+    ("base64url", "URL-safe base64 without padding"),
+    ("hex", "lowercase hexadecimal"),
+    ("base32", "RFC 4648 base32 with padding"),
+    ("crockford", "Crockford base32, no padding"),
+    ("base58", "base58 with the Bitcoin alphabet"),
+    ("z85", "ZeroMQ Z85; needs a multiple of 4 bytes"),
+    ("ascii85", "Adobe ascii85 between <~ and ~>"),
+    (
+        "percent",
+        "percent-encoded, keeping RFC 3986 unreserved characters",
+    ),
+    ("shell", "a single-quoted POSIX shell word"),
+    ("json", "a JSON string literal"),
+    ("yaml", "a single-quoted YAML scalar"),
+    ("xml", "XML text or attribute value with entities escaped"),
+];
+
+const RFC4648_BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const CROCKFORD_BASE32: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const BASE58: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const Z85: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+/// The formats that encode arbitrary bytes, as opposed to quoting text.
+pub const BYTE_ENCODINGS: &[&str] = &[
+    "base64",
+    "base64url",
+    "hex",
+    "base32",
+    "crockford",
+    "base58",
+    "z85",
+    "ascii85",
+    "percent",
+];
+
+/// Encodes `text` in the named format (case-insensitive).
+pub fn encode(text: &str, format: &str) -> Result<String> {
+    Ok(match format.to_lowercase().as_str() {
+        "plain" => text.to_string(),
+        "shell" => format!("'{}'", text.replace('\'', r"'\''")),
+        "json" => json_string(text),
+        "yaml" => format!("'{}'", text.replace('\'', "''")),
+        "xml" => xml_escape(text),
+        name if BYTE_ENCODINGS.contains(&name) => encode_bytes(text.as_bytes(), name)?,
+        _ => {
+            return Err(Error::Format(format!(
+                "Unsupported format: {} (valid: {})",
+                format,
+                FORMATS
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )))
+        }
+    })
+}
+
+/// Encodes raw `bytes` with one of `BYTE_ENCODINGS` (case-insensitive).
+pub fn encode_bytes(bytes: &[u8], encoding: &str) -> Result<String> {
+    Ok(match encoding.to_lowercase().as_str() {
+        "base64" => general_purpose::STANDARD.encode(bytes),
+        "base64url" => general_purpose::URL_SAFE_NO_PAD.encode(bytes),
+        "hex" => hex::encode(bytes),
+        "base32" => base32(bytes, RFC4648_BASE32, true),
+        "crockford" => base32(bytes, CROCKFORD_BASE32, false),
+        "base58" => base58(bytes),
+        "z85" => z85(bytes)?,
+        "ascii85" => ascii85(bytes),
+        "percent" => percent(bytes),
+        _ => {
+            return Err(Error::Format(format!(
+                "Unsupported encoding: {} (valid: {})",
+                encoding,
+                BYTE_ENCODINGS.join(", ")
+            )))
+        }
+    })
+}
+
+/// Base32 over 5-bit groups, most significant bits first.
+fn base32(bytes: &[u8], alphabet: &[u8; 32], pad: bool) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(alphabet[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(alphabet[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    if pad {
+        while !out.len().is_multiple_of(8) {
+            out.push('=');
+        }
+    }
+    out
+}
+
+/// Base58 treats the input as one big-endian number; each leading zero
+/// byte becomes a leading `1`.
+fn base58(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|&&byte| byte == 0).count();
+    // Little-endian base-58 digits of the number
+    let mut digits: Vec<u8> = Vec::with_capacity(bytes.len() * 138 / 100 + 1);
+    for &byte in &bytes[zeros..] {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    std::iter::repeat_n('1', zeros)
+        .chain(
+            digits
+                .iter()
+                .rev()
+                .map(|&digit| BASE58[digit as usize] as char),
+        )
+        .collect()
+}
+
+/// Writes the five base-85 digits of `value`, most significant first.
+fn base85_digits(value: u32, count: usize, alphabet: impl Fn(u32) -> char, out: &mut String) {
+    let mut digits = [0u32; 5];
+    let mut value = value;
+    for digit in digits.iter_mut().rev() {
+        *digit = value % 85;
+        value /= 85;
+    }
+    out.extend(digits[..count].iter().map(|&digit| alphabet(digit)));
+}
+
+fn z85(bytes: &[u8]) -> Result<String> {
+    if !bytes.len().is_multiple_of(4) {
+        return Err(Error::Format(format!(
+            "z85 needs a multiple of 4 bytes, got {}; use ascii85 instead",
+            bytes.len()
+        )));
+    }
+    let mut out = String::with_capacity(bytes.len() / 4 * 5);
+    for chunk in bytes.chunks(4) {
+        let value = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        base85_digits(value, 5, |digit| Z85[digit as usize] as char, &mut out);
+    }
+    Ok(out)
+}
+
+/// Adobe's variant: digits are `!` to `u`, an all-zero group is `z`, and a
+/// final partial group of n bytes is zero-padded and cut to n + 1 digits.
+fn ascii85(bytes: &[u8]) -> String {
+    let mut out = String::from("<~");
+    for chunk in bytes.chunks(4) {
+        let mut group = [0u8; 4];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let value = u32::from_be_bytes(group);
+        if value == 0 && chunk.len() == 4 {
+            out.push('z');
+        } else {
+            let alphabet = |digit: u32| char::from(b'!' + digit as u8);
+            base85_digits(value, chunk.len() + 1, alphabet, &mut out);
+        }
+    }
+    out.push_str("~>");
+    out
+}
+
+fn percent(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 3);
+    for &byte in bytes {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,41 @@
+/// Errors returned by the library. Every variant but `Io` carries a message
+/// meant for the user; the variant says which input was at fault.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Generator settings that can't produce a password, such as an empty
+    /// character set or class limits that contradict each other.
+    #[error("{0}")]
+    Generator(String),
+    /// An unknown `CharsetPreset` name.
+    #[error("{0}")]
+    Charset(String),
+    #[error("{0}")]
+    Pattern(String),
+    #[error("{0}")]
+    Regex(String),
+    /// Invalid `passwordrules`, or rules no password can satisfy.
+    #[error("{0}")]
+    Rules(String),
+    #[error("{0}")]
+    Policy(String),
+    #[error("{0}")]
+    Wordlist(String),
+    #[error("{0}")]
+    Passphrase(String),
+    /// A token size out of range.
+    #[error("{0}")]
+    Token(String),
+    /// An unknown format or encoding, or input it can't encode.
+    #[error("{0}")]
+    Format(String),
+    /// A hashing or verification failure, or unsupported hash parameters.
+    #[error("{0}")]
+    Hash(String),
+    /// An unreadable or malformed breach database.
+    #[error("{0}")]
+    Breach(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
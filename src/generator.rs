@@ -1,11 +1,188 @@
-use anyhow::Result;
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::seq::SliceRandom;
 use rand::Rng;
 use rand_core::OsRng;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::cell::OnceCell;
 use std::collections::HashSet;
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+use crate::pattern::{Pattern, Slot};
+use crate::regex::{Automaton, Regex};
+use crate::rules::{longest_run, PasswordRules};
+use crate::secret::SecretString;
+
+/// Argon2id cost for site-derived passwords. Changing any of these changes
+/// every derived password, so they are fixed rather than configurable.
+const DERIVE_MEMORY_KIB: u32 = 64 * 1024;
+const DERIVE_ITERATIONS: u32 = 3;
+const DERIVE_PARALLELISM: u32 = 1;
+
+/// Candidates drawn before giving up on a derived password that must also
+/// satisfy the class policy.
+const DERIVE_MAX_ATTEMPTS: usize = 10_000;
+
+/// Character classes that a generator policy can place count limits on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharClass {
+    Uppercase,
+    Lowercase,
+    Number,
+    Special,
+}
+
+impl CharClass {
+    pub const ALL: [CharClass; 4] = [
+        CharClass::Uppercase,
+        CharClass::Lowercase,
+        CharClass::Number,
+        CharClass::Special,
+    ];
+
+    /// Classifies a character; anything that is not an ASCII letter or digit
+    /// counts as special.
+    pub fn of(c: char) -> Self {
+        if c.is_ascii_uppercase() {
+            CharClass::Uppercase
+        } else if c.is_ascii_lowercase() {
+            CharClass::Lowercase
+        } else if c.is_ascii_digit() {
+            CharClass::Number
+        } else {
+            CharClass::Special
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CharClass::Uppercase => "uppercase",
+            CharClass::Lowercase => "lowercase",
+            CharClass::Number => "number",
+            CharClass::Special => "special",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Named character sets selectable with `--charset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharsetPreset {
+    Alphanumeric,
+    ShellSafe,
+    UrlSafe,
+    XmlSafe,
+    Hex,
+    Base58,
+}
+
+impl CharsetPreset {
+    pub const ALL: [CharsetPreset; 6] = [
+        CharsetPreset::Alphanumeric,
+        CharsetPreset::ShellSafe,
+        CharsetPreset::UrlSafe,
+        CharsetPreset::XmlSafe,
+        CharsetPreset::Hex,
+        CharsetPreset::Base58,
+    ];
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name() == name.to_lowercase())
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|preset| preset.name()).collect();
+                Error::Charset(format!(
+                    "Unknown charset: {} (expected one of: {})",
+                    name,
+                    names.join(", ")
+                ))
+            })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CharsetPreset::Alphanumeric => "alphanumeric",
+            CharsetPreset::ShellSafe => "shell-safe",
+            CharsetPreset::UrlSafe => "url-safe",
+            CharsetPreset::XmlSafe => "xml-safe",
+            CharsetPreset::Hex => "hex",
+            CharsetPreset::Base58 => "base58",
+        }
+    }
+
+    pub fn chars(self) -> &'static str {
+        match self {
+            CharsetPreset::Alphanumeric => {
+                "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789"
+            }
+            // Characters that never need quoting in a POSIX shell word
+            CharsetPreset::ShellSafe => {
+                "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789%+,-./:=@_"
+            }
+            // RFC 3986 unreserved characters
+            CharsetPreset::UrlSafe => {
+                "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-._~"
+            }
+            // Printable ASCII without the XML metacharacters & < > ' "
+            CharsetPreset::XmlSafe => {
+                "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789!#$%()*+,-./:;=?@[]^_{|}~"
+            }
+            CharsetPreset::Hex => "0123456789abcdef",
+            // Bitcoin alphabet: no 0, O, I or l
+            CharsetPreset::Base58 => "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz",
+        }
+    }
+}
+
+/// Candidates drawn before giving up on a password that must satisfy a
+/// set of password rules.
+const RULES_MAX_ATTEMPTS: usize = 100_000;
+
+/// Characters added by `include_special`.
+pub const SPECIAL_CHARS: &str = "!@#$%^&*()_+-=[]{}|;:,.<>?";
+
+/// Consonants and vowels for pronounceable passwords. Letters that are easy
+/// to mishear or misspell over the phone (c, q, w, x, y) are left out.
+const PRONOUNCEABLE_CONSONANTS: &str = "bdfghjklmnprstvz";
+const PRONOUNCEABLE_VOWELS: &str = "aeiou";
+const PRONOUNCEABLE_DIGITS: &str = "0123456789";
+const PRONOUNCEABLE_SYMBOLS: &str = "!@#$%&*+-=?";
+
+/// How `generate` builds a password.
+#[derive(Debug, Clone)]
+enum Mode {
+    /// Uniform over the charset, subject to the class limits.
+    Random,
+    /// Alternating consonant-vowel letters with `digits` digits and
+    /// `symbols` symbols inserted at random positions.
+    Pronounceable { digits: usize, symbols: usize },
+    /// One character per pattern slot; the length comes from the pattern.
+    Pattern(Pattern),
+    /// Uniform over the strings that satisfy a set of password rules.
+    Rules(PasswordRules),
+    /// Uniform over the strings a bounded regex matches. The automaton is
+    /// built on first use.
+    Regex {
+        regex: Regex,
+        automaton: OnceCell<Automaton>,
+    },
+}
 
 pub struct PasswordGenerator {
     charset: String,
     exclude_chars: HashSet<char>,
+    min_counts: [usize; 4],
+    max_counts: [Option<usize>; 4],
+    require_each: bool,
+    mode: Mode,
+    /// Class-policy counts, built on first use and reused while the policy
+    /// and length stay the same.
+    composition: OnceCell<CompositionTable>,
 }
 
 impl PasswordGenerator {
@@ -13,29 +190,45 @@ impl PasswordGenerator {
         Self {
             charset: String::new(),
             exclude_chars: HashSet::new(),
+            min_counts: [0; 4],
+            max_counts: [None; 4],
+            require_each: false,
+            mode: Mode::Random,
+            composition: OnceCell::new(),
         }
     }
 
     pub fn include_uppercase(&mut self) -> &mut Self {
-        self.charset.push_str("ABCDEFGHIJKLMNOPQRSTUVWXYZ");
-        self
+        self.include_chars("ABCDEFGHIJKLMNOPQRSTUVWXYZ")
     }
 
     pub fn include_lowercase(&mut self) -> &mut Self {
-        self.charset.push_str("abcdefghijklmnopqrstuvwxyz");
-        self
+        self.include_chars("abcdefghijklmnopqrstuvwxyz")
     }
 
     pub fn include_numbers(&mut self) -> &mut Self {
-        self.charset.push_str("0123456789");
-        self
+        self.include_chars("0123456789")
     }
 
     pub fn include_special(&mut self) -> &mut Self {
-        self.charset.push_str("!@#$%^&*()_+-=[]{}|;:,.<>?");
+        self.include_chars(SPECIAL_CHARS)
+    }
+
+    /// Adds arbitrary characters to the charset. Characters already present
+    /// are skipped so overlapping sets don't skew the distribution.
+    pub fn include_chars(&mut self, chars: &str) -> &mut Self {
+        for c in chars.chars() {
+            if !self.charset.contains(c) {
+                self.charset.push(c);
+            }
+        }
         self
     }
 
+    pub fn with_charset(&mut self, preset: CharsetPreset) -> &mut Self {
+        self.include_chars(preset.chars())
+    }
+
     pub fn include_all(&mut self) -> &mut Self {
         self.include_uppercase()
             .include_lowercase()
@@ -56,13 +249,75 @@ impl PasswordGenerator {
         self
     }
 
-    pub fn generate(&self, length: usize) -> Result<String> {
+    /// Requires at least `count` characters from `class` in every password.
+    pub fn min_count(&mut self, class: CharClass, count: usize) -> &mut Self {
+        self.min_counts[class.index()] = count;
+        self
+    }
+
+    /// Allows at most `count` characters from `class` in every password.
+    pub fn max_count(&mut self, class: CharClass, count: usize) -> &mut Self {
+        self.max_counts[class.index()] = Some(count);
+        self
+    }
+
+    /// Requires at least one character from every class left in the charset
+    /// after exclusions.
+    pub fn require_each_class(&mut self) -> &mut Self {
+        self.require_each = true;
+        self
+    }
+
+    /// Switches to pronounceable passwords: consonant-vowel syllables like
+    /// `dokatebu`, with `digits` digits and `symbols` symbols mixed in at
+    /// random positions. The charset and class limits don't apply, but the
+    /// exclusions do.
+    pub fn pronounceable(&mut self, digits: usize, symbols: usize) -> &mut Self {
+        self.mode = Mode::Pronounceable { digits, symbols };
+        self
+    }
+
+    /// Switches to generating passwords that match `pattern`, which then
+    /// fixes the length. Exclusions apply to every placeholder and class in
+    /// the pattern (but not to literal characters), and `*` draws from the
+    /// charset; class limits don't apply.
+    pub fn pattern(&mut self, pattern: Pattern) -> &mut Self {
+        self.mode = Mode::Pattern(pattern);
+        self
+    }
+
+    /// Switches to generating passwords that fully match `regex`, chosen
+    /// uniformly among every matching string; the regex fixes the length.
+    /// Exclusions apply to classes but not to literal characters, and must
+    /// be set before the first password is generated.
+    pub fn regex(&mut self, regex: Regex) -> &mut Self {
+        self.mode = Mode::Regex {
+            regex,
+            automaton: OnceCell::new(),
+        };
+        self
+    }
+
+    /// Switches to generating passwords that satisfy `rules`. The rules'
+    /// allowed characters replace the charset, exclusions still apply, and
+    /// the length must be within the rules' range.
+    pub fn rules(&mut self, rules: PasswordRules) -> &mut Self {
+        self.mode = Mode::Rules(rules);
+        self
+    }
+
+    fn has_policy(&self) -> bool {
+        self.require_each
+            || self.min_counts.iter().any(|&min| min > 0)
+            || self.max_counts.iter().any(Option::is_some)
+    }
+
+    /// Returns the de-duplicated charset with exclusions applied.
+    fn available_chars(&self) -> Result<Vec<char>> {
         if self.charset.is_empty() {
-            return Err(anyhow::anyhow!("No character set specified"));
+            return Err(Error::Generator("No character set specified".to_string()));
         }
 
-        let mut rng = OsRng;
-        let mut password = String::with_capacity(length);
         let available_chars: Vec<char> = self
             .charset
             .chars()
@@ -70,16 +325,705 @@ impl PasswordGenerator {
             .collect();
 
         if available_chars.is_empty() {
-            return Err(anyhow::anyhow!("No characters available after exclusions"));
+            return Err(Error::Generator(
+                "No characters available after exclusions".to_string(),
+            ));
+        }
+
+        Ok(available_chars)
+    }
+
+    /// Resolves the effective per-class `(mins, maxs)` for a password of
+    /// `length` characters, rejecting policies that can't be satisfied.
+    fn class_limits(
+        &self,
+        groups: &[Vec<char>; 4],
+        length: usize,
+    ) -> Result<([usize; 4], [usize; 4])> {
+        let mut mins = self.min_counts;
+        let mut maxs = [0usize; 4];
+        for class in CharClass::ALL {
+            let i = class.index();
+            if self.require_each && !groups[i].is_empty() {
+                mins[i] = mins[i].max(1);
+            }
+            if mins[i] > 0 && groups[i].is_empty() {
+                return Err(Error::Generator(format!(
+                    "Policy requires {} {} character(s) but none are available",
+                    mins[i],
+                    class.name()
+                )));
+            }
+            maxs[i] = if groups[i].is_empty() {
+                0
+            } else {
+                self.max_counts[i].unwrap_or(length).min(length)
+            };
+            if mins[i] > maxs[i] {
+                return Err(Error::Generator(format!(
+                    "Minimum {} count ({}) exceeds its maximum ({})",
+                    class.name(),
+                    mins[i],
+                    maxs[i]
+                )));
+            }
+        }
+
+        if mins.iter().sum::<usize>() > length {
+            return Err(Error::Generator(format!(
+                "Policy minimums require more than {} characters",
+                length
+            )));
+        }
+        if maxs.iter().sum::<usize>() < length {
+            return Err(Error::Generator(format!(
+                "Policy maximums cannot fill a {}-character password",
+                length
+            )));
+        }
+
+        Ok((mins, maxs))
+    }
+
+    pub fn generate(&self, length: usize) -> Result<SecretString> {
+        if let Mode::Pronounceable { digits, symbols } = self.mode {
+            return self.generate_pronounceable(length, digits, symbols);
+        }
+        if let Mode::Regex { regex, automaton } = &self.mode {
+            return Ok(self.regex_automaton(regex, automaton)?.sample(&mut OsRng));
+        }
+        if let Mode::Rules(rules) = &self.mode {
+            return self.generate_with_rules(rules, length);
+        }
+        if let Mode::Pattern(pattern) = &self.mode {
+            let mut rng = OsRng;
+            return Ok(self
+                .pattern_sets(pattern)?
+                .iter()
+                .map(|set| set[rng.gen_range(0..set.len())])
+                .collect());
+        }
+
+        let mut rng = OsRng;
+        let available_chars = self.available_chars()?;
+
+        if self.has_policy() {
+            return self.generate_with_policy(&available_chars, length);
+        }
+
+        Ok((0..length)
+            .map(|_| available_chars[rng.gen_range(0..available_chars.len())])
+            .collect())
+    }
+
+    /// Returns an iterator over `count` distinct passwords of `length`
+    /// characters, each generated like `generate`. Fails up front if fewer
+    /// than `count` distinct passwords exist with the current settings.
+    pub fn batch(&self, length: usize, count: usize) -> Result<Batch<'_>> {
+        let ln_space = self.ln_password_space(length)?;
+        if count > 0 && (count as f64).ln() > ln_space + 1e-9 {
+            return Err(Error::Generator(format!(
+                "Only {:.0} distinct passwords exist with these settings; cannot generate {} unique ones",
+                ln_space.exp(),
+                count
+            )));
+        }
+
+        Ok(Batch {
+            generator: self,
+            length,
+            remaining: count,
+            seen: HashSet::with_capacity(count),
+        })
+    }
+
+    /// Exact entropy in bits of a `length`-character password from
+    /// `generate`, which picks uniformly among every password it can return.
+    pub fn entropy(&self, length: usize) -> Result<f64> {
+        Ok(self.ln_password_space(length)? / std::f64::consts::LN_2)
+    }
+
+    /// Natural log of the number of distinct passwords `generate` can return.
+    fn ln_password_space(&self, length: usize) -> Result<f64> {
+        if let Mode::Pronounceable { digits, symbols } = self.mode {
+            let sets = self.pronounceable_sets(length, digits, symbols)?;
+            let letters = length - digits - symbols;
+            let ln = |set: &Vec<char>| (set.len() as f64).ln();
+            let ln_fact = |n: usize| (1..=n).map(|k| (k as f64).ln()).sum::<f64>();
+            // Letters alternate consonant/vowel; the digits and symbols can
+            // sit in any length! / (letters! digits! symbols!) arrangement.
+            return Ok(letters.div_ceil(2) as f64 * ln(&sets[0])
+                + (letters / 2) as f64 * ln(&sets[1])
+                + digits as f64 * ln(&sets[2])
+                + symbols as f64 * ln(&sets[3])
+                + ln_fact(length)
+                - ln_fact(letters)
+                - ln_fact(digits)
+                - ln_fact(symbols));
+        }
+        if let Mode::Regex { regex, automaton } = &self.mode {
+            return Ok(self.regex_automaton(regex, automaton)?.ln_size());
+        }
+        if let Mode::Rules(rules) = &self.mode {
+            let (allowed, required) = self.rules_sets(rules, length)?;
+            let fraction = rules_fraction(&allowed, &required, length, rules.max_consecutive());
+            return Ok(length as f64 * (allowed.len() as f64).ln() + fraction.ln());
+        }
+        if let Mode::Pattern(pattern) = &self.mode {
+            return Ok(self
+                .pattern_sets(pattern)?
+                .iter()
+                .map(|set| (set.len() as f64).ln())
+                .sum());
+        }
+
+        let available_chars = self.available_chars()?;
+        if !self.has_policy() {
+            return Ok(length as f64 * (available_chars.len() as f64).ln());
+        }
+
+        let groups = group_by_class(&available_chars);
+        let (mins, maxs) = self.class_limits(&groups, length)?;
+        let sizes = groups.each_ref().map(|group| group.len());
+        Ok(self.composition_table(&sizes, &mins, &maxs, length).ln_ways[0][length])
+    }
+
+    /// The composition table for these class limits. The first table built
+    /// is kept, since a generator is normally used with a single policy and
+    /// length; any other combination gets a fresh table.
+    fn composition_table(
+        &self,
+        sizes: &[usize; 4],
+        mins: &[usize; 4],
+        maxs: &[usize; 4],
+        length: usize,
+    ) -> Cow<'_, CompositionTable> {
+        let cached = self
+            .composition
+            .get_or_init(|| CompositionTable::new(sizes, mins, maxs, length));
+        if cached.is_for(sizes, mins, maxs, length) {
+            Cow::Borrowed(cached)
+        } else {
+            Cow::Owned(CompositionTable::new(sizes, mins, maxs, length))
+        }
+    }
+
+    /// Deterministically derives the password for `site` and `username` from a
+    /// master secret, so it can be recreated on any machine without a vault.
+    /// Bump `counter` to rotate a site's password.
+    ///
+    /// The master secret is stretched with Argon2id and the result keys a
+    /// SHA-256 stream that is mapped onto the charset by rejection sampling.
+    /// Candidates that break the class policy are discarded in favour of the
+    /// next one, which keeps the result uniform over valid passwords.
+    pub fn derive(
+        &self,
+        master: &str,
+        site: &str,
+        username: &str,
+        counter: u32,
+        length: usize,
+    ) -> Result<SecretString> {
+        let available_chars = self.available_chars()?;
+        let groups = group_by_class(&available_chars);
+        let (mins, maxs) = self.class_limits(&groups, length)?;
+
+        // Site names are case-insensitive, so normalise them before salting
+        let site = site.trim().to_lowercase();
+        let mut salt = b"passgen-derive-v1".to_vec();
+        for field in [site.as_bytes(), username.as_bytes()] {
+            salt.extend_from_slice(&(field.len() as u32).to_be_bytes());
+            salt.extend_from_slice(field);
+        }
+        salt.extend_from_slice(&counter.to_be_bytes());
+
+        let params = Params::new(
+            DERIVE_MEMORY_KIB,
+            DERIVE_ITERATIONS,
+            DERIVE_PARALLELISM,
+            Some(32),
+        )
+        .map_err(|e| Error::Generator(format!("Invalid key derivation parameters: {}", e)))?;
+        let mut seed = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(master.as_bytes(), &salt, seed.as_mut())
+            .map_err(|e| Error::Generator(format!("Key derivation failed: {}", e)))?;
+
+        let mut stream = KeyStream::new(seed.as_ref(), &available_chars, length);
+        for _ in 0..DERIVE_MAX_ATTEMPTS {
+            let password: SecretString = (0..length)
+                .map(|_| available_chars[stream.next_index(available_chars.len())])
+                .collect();
+            if satisfies_limits(password.expose(), &mins, &maxs) {
+                return Ok(password);
+            }
+        }
+
+        Err(Error::Generator(
+            "Could not derive a password satisfying the class limits; try relaxing them"
+                .to_string(),
+        ))
+    }
+
+    /// Returns the consonant, vowel, digit and symbol sets with exclusions
+    /// applied, checking that every set the password needs is non-empty.
+    fn pronounceable_sets(
+        &self,
+        length: usize,
+        digits: usize,
+        symbols: usize,
+    ) -> Result<[Vec<char>; 4]> {
+        if self.has_policy() {
+            return Err(Error::Generator(
+                "Class limits can't be combined with pronounceable passwords".to_string(),
+            ));
+        }
+        if digits + symbols > length {
+            return Err(Error::Generator(format!(
+                "{} digits and {} symbols don't fit in {} characters",
+                digits, symbols, length
+            )));
+        }
+
+        let letters = length - digits - symbols;
+        let sets = [
+            PRONOUNCEABLE_CONSONANTS,
+            PRONOUNCEABLE_VOWELS,
+            PRONOUNCEABLE_DIGITS,
+            PRONOUNCEABLE_SYMBOLS,
+        ]
+        .map(|set| {
+            set.chars()
+                .filter(|c| !self.exclude_chars.contains(c))
+                .collect::<Vec<char>>()
+        });
+        let needed = [letters > 0, letters > 1, digits > 0, symbols > 0];
+        let names = ["consonants", "vowels", "digits", "symbols"];
+        for i in 0..4 {
+            if needed[i] && sets[i].is_empty() {
+                return Err(Error::Generator(format!(
+                    "No {} left for a pronounceable password after exclusions",
+                    names[i]
+                )));
+            }
+        }
+
+        Ok(sets)
+    }
+
+    /// Resolves the rules' allowed and required characters with exclusions
+    /// applied, checking the length and that some password satisfies them.
+    fn rules_sets(
+        &self,
+        rules: &PasswordRules,
+        length: usize,
+    ) -> Result<(Vec<char>, Vec<Vec<char>>)> {
+        if self.has_policy() {
+            return Err(Error::Generator(
+                "Class limits can't be combined with password rules".to_string(),
+            ));
+        }
+        rules.check_length(length)?;
+
+        let keep = |chars: &[char]| -> Vec<char> {
+            chars
+                .iter()
+                .copied()
+                .filter(|c| !self.exclude_chars.contains(c))
+                .collect()
+        };
+        let allowed = keep(rules.allowed());
+        if allowed.is_empty() {
+            return Err(Error::Generator(
+                "No characters available after exclusions".to_string(),
+            ));
+        }
+        let required = rules
+            .required()
+            .iter()
+            .map(|(rule, chars)| {
+                let chars = keep(chars);
+                if chars.is_empty() {
+                    return Err(Error::Generator(format!(
+                        "Required class {} has no characters left after exclusions",
+                        rule
+                    )));
+                }
+                Ok(chars)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if rules_fraction(&allowed, &required, length, rules.max_consecutive()) <= 0.0 {
+            return Err(Error::Generator(format!(
+                "No {}-character password satisfies these rules",
+                length
+            )));
+        }
+        Ok((allowed, required))
+    }
+
+    /// Draws uniformly from the allowed characters until a candidate meets
+    /// every rule, which keeps the result uniform over valid passwords.
+    fn generate_with_rules(&self, rules: &PasswordRules, length: usize) -> Result<SecretString> {
+        let (allowed, required) = self.rules_sets(rules, length)?;
+        let mut rng = OsRng;
+
+        for _ in 0..RULES_MAX_ATTEMPTS {
+            let candidate: SecretString = (0..length)
+                .map(|_| allowed[rng.gen_range(0..allowed.len())])
+                .collect();
+            if required
+                .iter()
+                .all(|set| candidate.expose().chars().any(|c| set.contains(&c)))
+                && rules
+                    .max_consecutive()
+                    .is_none_or(|max| longest_run(candidate.expose()) <= max)
+            {
+                return Ok(candidate);
+            }
+        }
+
+        Err(Error::Generator(
+            "Could not generate a password satisfying these rules; try a longer length".to_string(),
+        ))
+    }
+
+    fn regex_automaton<'a>(
+        &self,
+        regex: &Regex,
+        automaton: &'a OnceCell<Automaton>,
+    ) -> Result<&'a Automaton> {
+        if let Some(automaton) = automaton.get() {
+            return Ok(automaton);
+        }
+        if self.has_policy() {
+            return Err(Error::Generator(
+                "Class limits can't be combined with a regex".to_string(),
+            ));
+        }
+        let built = regex.automaton(&self.exclude_chars)?;
+        Ok(automaton.get_or_init(|| built))
+    }
+
+    /// Resolves each pattern slot to the characters it can produce, with
+    /// exclusions applied to everything but literals.
+    fn pattern_sets(&self, pattern: &Pattern) -> Result<Vec<Vec<char>>> {
+        if self.has_policy() {
+            return Err(Error::Generator(
+                "Class limits can't be combined with a pattern".to_string(),
+            ));
+        }
+
+        pattern
+            .slots()
+            .iter()
+            .enumerate()
+            .map(|(position, slot)| match slot {
+                Slot::Literal(c) => Ok(vec![*c]),
+                Slot::Charset => self.available_chars(),
+                Slot::Set(set) => {
+                    let set: Vec<char> = set
+                        .iter()
+                        .copied()
+                        .filter(|c| !self.exclude_chars.contains(c))
+                        .collect();
+                    if set.is_empty() {
+                        return Err(Error::Generator(format!(
+                            "Position {} of pattern {} has no characters left after exclusions",
+                            position + 1,
+                            pattern.source()
+                        )));
+                    }
+                    Ok(set)
+                }
+            })
+            .collect()
+    }
+
+    fn generate_pronounceable(
+        &self,
+        length: usize,
+        digits: usize,
+        symbols: usize,
+    ) -> Result<SecretString> {
+        let sets = self.pronounceable_sets(length, digits, symbols)?;
+        let letters = length - digits - symbols;
+
+        // 0 = letter, 2 = digit, 3 = symbol; shuffling picks every
+        // arrangement with equal probability.
+        let mut rng = OsRng;
+        let mut slots: Zeroizing<Vec<usize>> = Zeroizing::new(
+            std::iter::repeat_n(0, letters)
+                .chain(std::iter::repeat_n(2, digits))
+                .chain(std::iter::repeat_n(3, symbols))
+                .collect(),
+        );
+        slots.shuffle(&mut rng);
+
+        let mut letter_index = 0;
+        Ok(slots
+            .iter()
+            .map(|&slot| {
+                let set = if slot == 0 {
+                    letter_index += 1;
+                    // Letters alternate consonant, vowel, consonant, ...
+                    &sets[(letter_index - 1) % 2]
+                } else {
+                    &sets[slot]
+                };
+                set[rng.gen_range(0..set.len())]
+            })
+            .collect())
+    }
+
+    /// Generates a password that satisfies the per-class limits while staying
+    /// uniformly distributed over every password that satisfies them.
+    ///
+    /// The number of characters taken from each class is drawn in proportion
+    /// to how many valid passwords have that composition, then the class
+    /// positions are shuffled and each position is filled from its class.
+    fn generate_with_policy(
+        &self,
+        available_chars: &[char],
+        length: usize,
+    ) -> Result<SecretString> {
+        let groups = group_by_class(available_chars);
+        let (mins, maxs) = self.class_limits(&groups, length)?;
+
+        let sizes = groups.each_ref().map(|group| group.len());
+        let table = self.composition_table(&sizes, &mins, &maxs, length);
+        let counts = sample_class_counts(&table, &mins, &maxs, length);
+
+        let mut rng = OsRng;
+        let mut classes: Zeroizing<Vec<usize>> = Zeroizing::new(Vec::with_capacity(length));
+        for (i, &count) in counts.iter().enumerate() {
+            classes.extend(std::iter::repeat_n(i, count));
+        }
+        classes.shuffle(&mut rng);
+
+        Ok(classes
+            .iter()
+            .map(|&i| groups[i][rng.gen_range(0..groups[i].len())])
+            .collect())
+    }
+}
+
+/// Distinct passwords from `PasswordGenerator::batch`, generated lazily so
+/// large batches can be streamed.
+pub struct Batch<'a> {
+    generator: &'a PasswordGenerator,
+    length: usize,
+    remaining: usize,
+    seen: HashSet<SecretString>,
+}
+
+impl Iterator for Batch<'_> {
+    type Item = Result<SecretString>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        // Duplicates are redrawn, which keeps the batch a uniform sample
+        // without replacement. `batch` checked enough passwords exist.
+        loop {
+            match self.generator.generate(self.length) {
+                Ok(password) => {
+                    if self.seen.insert(password.clone()) {
+                        self.remaining -= 1;
+                        return Some(Ok(password));
+                    }
+                }
+                Err(e) => {
+                    self.remaining = 0;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// Deterministic byte stream made of `SHA-256(key || block)` blocks.
+///
+/// Derived passwords must never change between releases, so this is used
+/// instead of a `rand` RNG whose output may differ across crate versions.
+struct KeyStream {
+    key: [u8; 32],
+    block: u64,
+    buffer: Vec<u8>,
+}
+
+impl KeyStream {
+    /// Binds the stream to the output shape so that changing the length or
+    /// charset yields an unrelated password rather than a prefix of another.
+    fn new(seed: &[u8], chars: &[char], length: usize) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(seed);
+        hasher.update((length as u64).to_be_bytes());
+        hasher.update(chars.iter().collect::<String>().as_bytes());
+        Self {
+            key: hasher.finalize().into(),
+            block: 0,
+            buffer: Vec::new(),
+        }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        if self.buffer.len() < 4 {
+            let mut hasher = Sha256::new();
+            hasher.update(self.key);
+            hasher.update(self.block.to_be_bytes());
+            self.block += 1;
+            self.buffer.extend_from_slice(&hasher.finalize());
+        }
+        let bytes: Vec<u8> = self.buffer.drain(..4).collect();
+        u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    /// Returns an index below `bound` without modulo bias by rejecting values
+    /// from the incomplete range at the top of `u32`.
+    fn next_index(&mut self, bound: usize) -> usize {
+        let bound = bound as u64;
+        let limit = (1u64 << 32) / bound * bound;
+        loop {
+            let value = self.next_u32() as u64;
+            if value < limit {
+                return (value % bound) as usize;
+            }
+        }
+    }
+}
+
+fn satisfies_limits(password: &str, mins: &[usize; 4], maxs: &[usize; 4]) -> bool {
+    let mut counts = [0usize; 4];
+    for c in password.chars() {
+        counts[CharClass::of(c).index()] += 1;
+    }
+    (0..4).all(|i| counts[i] >= mins[i] && counts[i] <= maxs[i])
+}
+
+fn group_by_class(chars: &[char]) -> [Vec<char>; 4] {
+    let mut groups: [Vec<char>; 4] = Default::default();
+    for &c in chars {
+        groups[CharClass::of(c).index()].push(c);
+    }
+    groups
+}
+
+/// Picks how many characters each class contributes, weighting every
+/// composition by the number of passwords it produces:
+/// `length! / prod(k_i!) * prod(size_i ^ k_i)`.
+///
+/// Weights are kept in log space so long passwords don't overflow.
+fn sample_class_counts(
+    table: &CompositionTable,
+    mins: &[usize; 4],
+    maxs: &[usize; 4],
+    length: usize,
+) -> [usize; 4] {
+    let (ln_choose, ln_size, ln_ways) = (
+        |n: usize, k: usize| table.ln_choose(n, k),
+        &table.ln_size,
+        &table.ln_ways,
+    );
+
+    let mut rng = OsRng;
+    let mut counts = [0usize; 4];
+    let mut remaining = length;
+    for j in 0..4 {
+        let total = ln_ways[j][remaining];
+        let mut target: f64 = rng.gen();
+        let upper = maxs[j].min(remaining);
+        let mut chosen = upper;
+        for k in mins[j]..=upper {
+            let term =
+                ln_choose(remaining, k) + k as f64 * ln_size[j] + ln_ways[j + 1][remaining - k];
+            target -= (term - total).exp();
+            if target < 0.0 {
+                chosen = k;
+                break;
+            }
         }
+        // Rounding can leave `target` marginally positive; fall back to the
+        // largest composition that still leaves the rest fillable.
+        while ln_ways[j + 1][remaining - chosen] == f64::NEG_INFINITY {
+            chosen -= 1;
+        }
+        counts[j] = chosen;
+        remaining -= chosen;
+    }
+
+    counts
+}
+
+/// Log-space counts of the passwords that satisfy a set of class limits.
+///
+/// Building it costs O(length²), so it is cached by `PasswordGenerator`.
+#[derive(Clone)]
+struct CompositionTable {
+    /// The `(sizes, mins, maxs, length)` the table was built for.
+    limits: ([usize; 4], [usize; 4], [usize; 4], usize),
+    ln_fact: Vec<f64>,
+    ln_size: [f64; 4],
+    /// `ln_ways[j][r]`: log of the number of ways to fill `r` positions
+    /// using classes `j..` while respecting their limits.
+    ln_ways: Vec<Vec<f64>>,
+}
+
+impl CompositionTable {
+    fn new(sizes: &[usize; 4], mins: &[usize; 4], maxs: &[usize; 4], length: usize) -> Self {
+        let mut ln_fact = vec![0.0f64; length + 1];
+        for n in 1..=length {
+            ln_fact[n] = ln_fact[n - 1] + (n as f64).ln();
+        }
+        let mut table = Self {
+            limits: (*sizes, *mins, *maxs, length),
+            ln_fact,
+            ln_size: sizes.map(|s| (s.max(1) as f64).ln()),
+            ln_ways: vec![vec![f64::NEG_INFINITY; length + 1]; 5],
+        };
 
-        for _ in 0..length {
-            let index = rng.gen_range(0..available_chars.len());
-            password.push(available_chars[index]);
+        table.ln_ways[4][0] = 0.0;
+        for j in (0..4).rev() {
+            for r in 0..=length {
+                let terms: Vec<f64> = (mins[j]..=maxs[j].min(r))
+                    .map(|k| {
+                        table.ln_choose(r, k)
+                            + k as f64 * table.ln_size[j]
+                            + table.ln_ways[j + 1][r - k]
+                    })
+                    .collect();
+                table.ln_ways[j][r] = log_sum_exp(&terms);
+            }
         }
+        table
+    }
+
+    fn is_for(
+        &self,
+        sizes: &[usize; 4],
+        mins: &[usize; 4],
+        maxs: &[usize; 4],
+        length: usize,
+    ) -> bool {
+        self.limits == (*sizes, *mins, *maxs, length)
+    }
+
+    fn ln_choose(&self, n: usize, k: usize) -> f64 {
+        self.ln_fact[n] - self.ln_fact[k] - self.ln_fact[n - k]
+    }
+}
 
-        Ok(password)
+fn log_sum_exp(terms: &[f64]) -> f64 {
+    let max = terms.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return max;
     }
+    max + terms.iter().map(|t| (t - max).exp()).sum::<f64>().ln()
 }
 
 impl Default for PasswordGenerator {
@@ -87,3 +1031,275 @@ impl Default for PasswordGenerator {
         Self::new()
     }
 }
+
+/// Fraction of the `allowed.len()^length` strings over `allowed` that use a
+/// character from every required set and repeat no character more than
+/// `max_consecutive` times in a row, by inclusion-exclusion over the
+/// required sets.
+fn rules_fraction(
+    allowed: &[char],
+    required: &[Vec<char>],
+    length: usize,
+    max_consecutive: Option<usize>,
+) -> f64 {
+    let mut by_size: std::collections::HashMap<usize, f64> = std::collections::HashMap::new();
+    let mut total = 0.0;
+
+    for subset in 0..(1usize << required.len()) {
+        let banned: HashSet<char> = required
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| subset & (1 << i) != 0)
+            .flat_map(|(_, set)| set.iter().copied())
+            .collect();
+        let size = allowed.iter().filter(|c| !banned.contains(c)).count();
+        let fraction = *by_size
+            .entry(size)
+            .or_insert_with(|| run_limited_fraction(size, allowed.len(), length, max_consecutive));
+        if subset.count_ones() % 2 == 0 {
+            total += fraction;
+        } else {
+            total -= fraction;
+        }
+    }
+
+    // The alternating sum leaves rounding residue where the true count is
+    // zero; anything worth less than half a string is that residue
+    let ln_strings = total.ln() + length as f64 * (allowed.len() as f64).ln();
+    if total <= 0.0 || ln_strings < 0.5f64.ln() {
+        return 0.0;
+    }
+    total
+}
+
+/// Number of `length`-character strings over `size` letters with no run
+/// longer than `max_run`, divided by `alphabet^length`.
+fn run_limited_fraction(
+    size: usize,
+    alphabet: usize,
+    length: usize,
+    max_run: Option<usize>,
+) -> f64 {
+    if length == 0 {
+        return 1.0;
+    }
+    let (size, alphabet) = (size as f64, alphabet as f64);
+    let Some(max_run) = max_run.filter(|&max_run| max_run < length) else {
+        return (size / alphabet).powi(length as i32);
+    };
+
+    // counts[i] covers strings of length i. Their last run has some length
+    // j, preceded by nothing (any letter starts it) or by a shorter valid
+    // string ending in a different letter.
+    let mut counts = vec![0.0; length + 1];
+    for i in 1..=length {
+        counts[i] = (1..=max_run.min(i))
+            .map(|j| {
+                let before = if j == i {
+                    size
+                } else {
+                    counts[i - j] * (size - 1.0)
+                };
+                before / alphabet.powi(j as i32)
+            })
+            .sum();
+    }
+    counts[length]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two uppercase, three lowercase, one digit and two specials.
+    const SMALL_CHARSET: &str = "ABxyz7!@";
+
+    /// A class with its optional minimum and maximum count.
+    type Limit = (CharClass, Option<usize>, Option<usize>);
+
+    /// Every string of `length` characters over `chars`.
+    fn all_strings(chars: &[char], length: usize) -> Vec<String> {
+        (0..length).fold(vec![String::new()], |prefixes, _| {
+            prefixes
+                .iter()
+                .flat_map(|prefix| chars.iter().map(move |&c| format!("{}{}", prefix, c)))
+                .collect()
+        })
+    }
+
+    fn small_generator(limits: &[Limit]) -> PasswordGenerator {
+        let mut generator = PasswordGenerator::new();
+        generator.include_chars(SMALL_CHARSET);
+        for &(class, min, max) in limits {
+            if let Some(min) = min {
+                generator.min_count(class, min);
+            }
+            if let Some(max) = max {
+                generator.max_count(class, max);
+            }
+        }
+        generator
+    }
+
+    fn policies() -> Vec<Vec<Limit>> {
+        vec![
+            vec![(CharClass::Number, Some(1), None)],
+            vec![(CharClass::Uppercase, Some(1), Some(2))],
+            vec![
+                (CharClass::Lowercase, Some(2), None),
+                (CharClass::Special, None, Some(1)),
+            ],
+            vec![
+                (CharClass::Uppercase, Some(1), Some(1)),
+                (CharClass::Number, Some(1), Some(2)),
+                (CharClass::Special, Some(1), None),
+            ],
+        ]
+    }
+
+    #[test]
+    fn composition_table_matches_exact_counts() {
+        let chars: Vec<char> = SMALL_CHARSET.chars().collect();
+        for limits in policies() {
+            // One generator across lengths also covers rebuilding the table
+            // when the cached one is for another length
+            let generator = small_generator(&limits);
+            for length in 3..=6 {
+                let groups = group_by_class(&chars);
+                let (mins, maxs) = generator.class_limits(&groups, length).unwrap();
+                let expected = all_strings(&chars, length)
+                    .iter()
+                    .filter(|password| satisfies_limits(password, &mins, &maxs))
+                    .count() as f64;
+
+                let sizes = groups.each_ref().map(|group| group.len());
+                let table = CompositionTable::new(&sizes, &mins, &maxs, length);
+                let counted = table.ln_ways[0][length].exp();
+                assert!(
+                    (counted - expected).abs() < 1e-6 * expected,
+                    "{:?} at length {}: {} != {}",
+                    limits,
+                    length,
+                    counted,
+                    expected
+                );
+
+                let entropy = generator.entropy(length).unwrap();
+                assert!((entropy - expected.log2()).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn require_each_matches_exact_count() {
+        let chars: Vec<char> = SMALL_CHARSET.chars().collect();
+        let mut generator = small_generator(&[]);
+        generator.require_each_class();
+        let expected = all_strings(&chars, 5)
+            .iter()
+            .filter(|password| satisfies_limits(password, &[1; 4], &[5; 4]))
+            .count() as f64;
+        assert!((generator.entropy(5).unwrap() - expected.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn policy_samples_meet_their_limits() {
+        let chars: Vec<char> = SMALL_CHARSET.chars().collect();
+        for limits in policies() {
+            let generator = small_generator(&limits);
+            for length in [4, 12] {
+                let groups = group_by_class(&chars);
+                let (mins, maxs) = generator.class_limits(&groups, length).unwrap();
+                for _ in 0..200 {
+                    let password = generator.generate(length).unwrap();
+                    assert_eq!(password.expose().chars().count(), length);
+                    assert!(
+                        satisfies_limits(password.expose(), &mins, &maxs),
+                        "{:?} broke {:?}",
+                        password.expose(),
+                        limits
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn impossible_policies_are_rejected() {
+        let generator = small_generator(&[(CharClass::Number, Some(2), Some(1))]);
+        assert!(generator.generate(8).is_err());
+        let generator = small_generator(&[
+            (CharClass::Uppercase, Some(3), None),
+            (CharClass::Lowercase, Some(3), None),
+        ]);
+        assert!(generator.generate(5).is_err());
+    }
+
+    /// Rules over small alphabets, including overlapping required classes.
+    const SMALL_RULES: &[&str] = &[
+        "required: [ab]",
+        "required: [ab]; required: [12]; allowed: [x]",
+        "required: [ab]; required: [b1]; required: [2]",
+        "allowed: [ab]; max-consecutive: 1",
+        "required: [a]; required: [1]; allowed: [b]; max-consecutive: 2",
+        "required: [ab]; required: [12]; required: [!]; max-consecutive: 1",
+    ];
+
+    #[test]
+    fn rules_fraction_matches_exact_counts() {
+        for source in SMALL_RULES {
+            let rules = PasswordRules::parse(source).unwrap();
+            let allowed = rules.allowed().to_vec();
+            let required: Vec<Vec<char>> = rules
+                .required()
+                .iter()
+                .map(|(_, chars)| chars.clone())
+                .collect();
+            let mut generator = PasswordGenerator::new();
+            generator.rules(rules.clone());
+
+            for length in 1..=6 {
+                let valid = all_strings(&allowed, length)
+                    .iter()
+                    .filter(|password| rules.violations(password).is_empty())
+                    .count() as f64;
+                let total = (allowed.len() as f64).powi(length as i32);
+
+                let fraction = rules_fraction(&allowed, &required, length, rules.max_consecutive());
+                assert!(
+                    (fraction * total - valid).abs() < 1e-6 * total,
+                    "{} at length {}: {} != {}",
+                    source,
+                    length,
+                    fraction * total,
+                    valid
+                );
+
+                if valid == 0.0 {
+                    assert!(generator.entropy(length).is_err(), "{}", source);
+                } else {
+                    let entropy = generator.entropy(length).unwrap();
+                    assert!((entropy - valid.log2()).abs() < 1e-9, "{}", source);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rules_samples_satisfy_the_rules() {
+        for source in SMALL_RULES {
+            let rules = PasswordRules::parse(source).unwrap();
+            let mut generator = PasswordGenerator::new();
+            generator.rules(rules.clone());
+            for _ in 0..100 {
+                let password = generator.generate(6).unwrap();
+                assert_eq!(
+                    rules.violations(password.expose()),
+                    Vec::<String>::new(),
+                    "{}",
+                    source
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,689 @@
+use argon2::password_hash::{self, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use base64::{engine::general_purpose, Engine as _};
+use rand_core::OsRng;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use subtle::ConstantTimeEq;
+
+use crate::error::{Error, Result};
+
+/// Argon2id defaults, following the OWASP password storage recommendation
+/// (19 MiB, 2 passes, 1 lane).
+pub const ARGON2_MEMORY_KIB: u32 = 19 * 1024;
+pub const ARGON2_ITERATIONS: u32 = 2;
+pub const ARGON2_PARALLELISM: u32 = 1;
+
+/// bcrypt work factor (2^cost rounds).
+pub const BCRYPT_COST: u32 = 12;
+
+/// scrypt defaults: N = 2^17, r = 8, p = 1.
+pub const SCRYPT_LOG_N: u8 = 17;
+pub const SCRYPT_BLOCK_SIZE: u32 = 8;
+pub const SCRYPT_PARALLELISM: u32 = 1;
+
+/// PBKDF2 iteration counts per OWASP for each PRF.
+pub const PBKDF2_SHA256_ITERATIONS: u32 = 600_000;
+pub const PBKDF2_SHA512_ITERATIONS: u32 = 210_000;
+
+/// Upper bounds on the costs `verify_password` will compute and
+/// `hash_password` will accept, so that neither a stored hash nor a request
+/// can make one call take minutes or gigabytes.
+pub const ARGON2_MAX_MEMORY_KIB: u32 = 1024 * 1024;
+pub const ARGON2_MAX_ITERATIONS: u32 = 10;
+pub const ARGON2_MAX_PARALLELISM: u32 = 16;
+pub const BCRYPT_MAX_COST: u32 = 16;
+/// scrypt needs 128 * r * N bytes; this caps it at 1 GiB.
+pub const SCRYPT_MAX_MEMORY: u64 = 1024 * 1024 * 1024;
+pub const SCRYPT_MAX_PARALLELISM: u32 = 16;
+pub const PBKDF2_MAX_ITERATIONS: u32 = 10_000_000;
+pub const SHA_CRYPT_MAX_ROUNDS: u32 = 10_000_000;
+
+/// bcrypt only looks at the first 72 bytes of its input.
+const BCRYPT_MAX_INPUT: usize = 72;
+
+/// Password hashing algorithms that produce self-describing strings
+/// (PHC format, or modular crypt format for bcrypt) with a random salt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Argon2id,
+    Bcrypt,
+    Scrypt,
+    Pbkdf2Sha256,
+    Pbkdf2Sha512,
+}
+
+impl HashAlgorithm {
+    pub const ALL: [HashAlgorithm; 5] = [
+        HashAlgorithm::Argon2id,
+        HashAlgorithm::Bcrypt,
+        HashAlgorithm::Scrypt,
+        HashAlgorithm::Pbkdf2Sha256,
+        HashAlgorithm::Pbkdf2Sha512,
+    ];
+
+    /// Looks an algorithm up by name, returning `None` for anything that is
+    /// not a password hashing algorithm (e.g. plain digests).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "argon2" | "argon2id" => Some(HashAlgorithm::Argon2id),
+            "bcrypt" => Some(HashAlgorithm::Bcrypt),
+            "scrypt" => Some(HashAlgorithm::Scrypt),
+            "pbkdf2" | "pbkdf2-sha256" => Some(HashAlgorithm::Pbkdf2Sha256),
+            "pbkdf2-sha512" => Some(HashAlgorithm::Pbkdf2Sha512),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Argon2id => "argon2id",
+            HashAlgorithm::Bcrypt => "bcrypt",
+            HashAlgorithm::Scrypt => "scrypt",
+            HashAlgorithm::Pbkdf2Sha256 => "pbkdf2-sha256",
+            HashAlgorithm::Pbkdf2Sha512 => "pbkdf2-sha512",
+        }
+    }
+}
+
+/// Optional cost overrides. Anything left unset uses the algorithm's
+/// recommended default; setting a parameter the algorithm does not have is
+/// an error rather than being silently ignored.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HashParams {
+    /// Argon2 memory in KiB.
+    pub memory_kib: Option<u32>,
+    /// Argon2 passes or PBKDF2 iterations.
+    pub iterations: Option<u32>,
+    /// Argon2 lanes or scrypt p.
+    pub parallelism: Option<u32>,
+    /// bcrypt work factor or scrypt log2(N).
+    pub cost: Option<u32>,
+}
+
+impl HashParams {
+    fn reject_unused(&self, algorithm: HashAlgorithm, used: &[&str]) -> Result<()> {
+        let given = [
+            ("memory", self.memory_kib.is_some()),
+            ("iterations", self.iterations.is_some()),
+            ("parallelism", self.parallelism.is_some()),
+            ("cost", self.cost.is_some()),
+        ];
+        for (param, set) in given {
+            if set && !used.contains(&param) {
+                return Err(Error::Hash(format!(
+                    "{} does not take a {} parameter (it takes: {})",
+                    algorithm.name(),
+                    param,
+                    used.join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rejects costs above the `*_MAX_*` limits for `algorithm`.
+    fn check_limits(&self, algorithm: HashAlgorithm) -> Result<()> {
+        match algorithm {
+            HashAlgorithm::Argon2id => check_argon2_cost(
+                self.memory_kib.unwrap_or(ARGON2_MEMORY_KIB),
+                self.iterations.unwrap_or(ARGON2_ITERATIONS),
+                self.parallelism.unwrap_or(ARGON2_PARALLELISM),
+            ),
+            HashAlgorithm::Bcrypt => check_cost(
+                "bcrypt cost",
+                self.cost.unwrap_or(BCRYPT_COST).into(),
+                BCRYPT_MAX_COST.into(),
+            ),
+            HashAlgorithm::Scrypt => {
+                let log_n = match self.cost {
+                    Some(cost) => u8::try_from(cost).map_err(|_| {
+                        Error::Hash(format!("scrypt cost (log2 N) is too large: {}", cost))
+                    })?,
+                    None => SCRYPT_LOG_N,
+                };
+                check_scrypt_cost(
+                    log_n,
+                    SCRYPT_BLOCK_SIZE,
+                    self.parallelism.unwrap_or(SCRYPT_PARALLELISM),
+                )
+            }
+            HashAlgorithm::Pbkdf2Sha256 | HashAlgorithm::Pbkdf2Sha512 => check_cost(
+                &format!("{} iterations", algorithm.name()),
+                self.iterations.unwrap_or(PBKDF2_SHA256_ITERATIONS).into(),
+                PBKDF2_MAX_ITERATIONS.into(),
+            ),
+        }
+    }
+}
+
+/// Fails if `value` is above `max`; `what` names the parameter.
+fn check_cost(what: &str, value: u64, max: u64) -> Result<()> {
+    if value > max {
+        return Err(Error::Hash(format!(
+            "{} {} is above the maximum of {}",
+            what, value, max
+        )));
+    }
+    Ok(())
+}
+
+fn check_argon2_cost(m_cost: u32, t_cost: u32, p_cost: u32) -> Result<()> {
+    check_cost(
+        "argon2 memory (KiB)",
+        m_cost.into(),
+        ARGON2_MAX_MEMORY_KIB.into(),
+    )?;
+    check_cost(
+        "argon2 iterations",
+        t_cost.into(),
+        ARGON2_MAX_ITERATIONS.into(),
+    )?;
+    check_cost(
+        "argon2 parallelism",
+        p_cost.into(),
+        ARGON2_MAX_PARALLELISM.into(),
+    )
+}
+
+fn check_scrypt_cost(log_n: u8, r: u32, p: u32) -> Result<()> {
+    let memory = 128u64
+        .saturating_mul(r.into())
+        .saturating_mul(1u64.checked_shl(log_n.into()).unwrap_or(u64::MAX));
+    if memory > SCRYPT_MAX_MEMORY {
+        return Err(Error::Hash(format!(
+            "scrypt ln={}, r={} needs more than the maximum of {} MiB",
+            log_n,
+            r,
+            SCRYPT_MAX_MEMORY / (1024 * 1024)
+        )));
+    }
+    check_cost(
+        "scrypt parallelism",
+        p.into(),
+        SCRYPT_MAX_PARALLELISM.into(),
+    )
+}
+
+/// Hashes a password with a fresh random salt, returning a string that can be
+/// stored as-is and later verified.
+pub fn hash_password(
+    password: &str,
+    algorithm: HashAlgorithm,
+    params: &HashParams,
+) -> Result<String> {
+    params.check_limits(algorithm)?;
+    let salt = SaltString::generate(&mut OsRng);
+
+    match algorithm {
+        HashAlgorithm::Argon2id => {
+            params.reject_unused(algorithm, &["memory", "iterations", "parallelism"])?;
+            let argon_params = argon2::Params::new(
+                params.memory_kib.unwrap_or(ARGON2_MEMORY_KIB),
+                params.iterations.unwrap_or(ARGON2_ITERATIONS),
+                params.parallelism.unwrap_or(ARGON2_PARALLELISM),
+                None,
+            )
+            .map_err(|e| Error::Hash(format!("Invalid argon2id parameters: {}", e)))?;
+            let hash = argon2::Argon2::new(
+                argon2::Algorithm::Argon2id,
+                argon2::Version::V0x13,
+                argon_params,
+            )
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| Error::Hash(format!("argon2id hashing failed: {}", e)))?;
+            Ok(hash.to_string())
+        }
+        HashAlgorithm::Bcrypt => {
+            params.reject_unused(algorithm, &["cost"])?;
+            if password.len() > BCRYPT_MAX_INPUT {
+                return Err(Error::Hash(format!(
+                    "bcrypt only uses the first {} bytes of input; use argon2id for longer passwords",
+                    BCRYPT_MAX_INPUT
+                )));
+            }
+            bcrypt::hash(password, params.cost.unwrap_or(BCRYPT_COST))
+                .map_err(|e| Error::Hash(format!("bcrypt hashing failed: {}", e)))
+        }
+        HashAlgorithm::Scrypt => {
+            params.reject_unused(algorithm, &["cost", "parallelism"])?;
+            let log_n = match params.cost {
+                Some(cost) => u8::try_from(cost).map_err(|_| {
+                    Error::Hash(format!("scrypt cost (log2 N) is too large: {}", cost))
+                })?,
+                None => SCRYPT_LOG_N,
+            };
+            let scrypt_params = scrypt::Params::new(
+                log_n,
+                SCRYPT_BLOCK_SIZE,
+                params.parallelism.unwrap_or(SCRYPT_PARALLELISM),
+                scrypt::Params::RECOMMENDED_LEN,
+            )
+            .map_err(|e| Error::Hash(format!("Invalid scrypt parameters: {}", e)))?;
+            let hash = scrypt::Scrypt
+                .hash_password_customized(password.as_bytes(), None, None, scrypt_params, &salt)
+                .map_err(|e| Error::Hash(format!("scrypt hashing failed: {}", e)))?;
+            Ok(hash.to_string())
+        }
+        HashAlgorithm::Pbkdf2Sha256 | HashAlgorithm::Pbkdf2Sha512 => {
+            params.reject_unused(algorithm, &["iterations"])?;
+            let (prf, default_rounds) = if algorithm == HashAlgorithm::Pbkdf2Sha256 {
+                (pbkdf2::Algorithm::Pbkdf2Sha256, PBKDF2_SHA256_ITERATIONS)
+            } else {
+                (pbkdf2::Algorithm::Pbkdf2Sha512, PBKDF2_SHA512_ITERATIONS)
+            };
+            let pbkdf2_params = pbkdf2::Params {
+                rounds: params.iterations.unwrap_or(default_rounds),
+                output_length: 32,
+            };
+            let hash = pbkdf2::Pbkdf2
+                .hash_password_customized(
+                    password.as_bytes(),
+                    Some(prf.ident()),
+                    None,
+                    pbkdf2_params,
+                    &salt,
+                )
+                .map_err(|e| Error::Hash(format!("{} hashing failed: {}", algorithm.name(), e)))?;
+            Ok(hash.to_string())
+        }
+    }
+}
+
+/// Outcome of checking a password against a stored hash.
+#[derive(Debug, Clone)]
+pub struct Verification {
+    /// The detected scheme, e.g. `argon2id`, `bcrypt`, `sha512-crypt`, `ssha`.
+    pub scheme: String,
+    pub matches: bool,
+    /// Whether the hash should be replaced with a fresh `hash_password`
+    /// result the next time the plaintext is available.
+    pub needs_rehash: bool,
+    /// Why a rehash is recommended.
+    pub reason: Option<String>,
+}
+
+impl Verification {
+    fn new(scheme: &str, matches: bool, weakness: Option<String>) -> Self {
+        Self {
+            scheme: scheme.to_string(),
+            matches,
+            needs_rehash: weakness.is_some(),
+            reason: weakness,
+        }
+    }
+}
+
+/// Checks `password` against a stored hash, detecting the scheme from the
+/// hash itself. Understands PHC strings (Argon2, scrypt, PBKDF2), bcrypt
+/// (`$2a$`, `$2b$`, `$2y$`), SHA-crypt (`$5$`, `$6$`) and LDAP
+/// `{SHA}`/`{SSHA}` style values.
+///
+/// A mismatch is reported in the result; an error means the hash itself
+/// could not be understood.
+pub fn verify_password(password: &str, hash: &str) -> Result<Verification> {
+    let hash = hash.trim();
+
+    if hash.starts_with("$2a$") || hash.starts_with("$2b$") || hash.starts_with("$2y$") {
+        return verify_bcrypt(password, hash);
+    }
+    if let Some(rest) = hash.strip_prefix("$5$") {
+        return verify_sha_crypt::<Sha256>(password, rest, "sha256-crypt", hash);
+    }
+    if let Some(rest) = hash.strip_prefix("$6$") {
+        return verify_sha_crypt::<Sha512>(password, rest, "sha512-crypt", hash);
+    }
+    if hash.starts_with('{') {
+        return verify_ldap(password, hash);
+    }
+    if hash.starts_with('$') {
+        return verify_phc(password, hash);
+    }
+
+    Err(Error::Hash(
+        "Unrecognized hash format (expected PHC, $2b$, $5$, $6$ or {SSHA} style)".to_string(),
+    ))
+}
+
+/// PHC algorithm identifiers `verify_phc` knows how to check.
+const PHC_SCHEMES: &[&str] = &[
+    "argon2id",
+    "argon2i",
+    "argon2d",
+    "scrypt",
+    "pbkdf2",
+    "pbkdf2-sha256",
+    "pbkdf2-sha512",
+];
+
+fn verify_phc(password: &str, hash: &str) -> Result<Verification> {
+    let id = hash[1..].split('$').next().unwrap_or_default();
+    if !PHC_SCHEMES.contains(&id) {
+        return Err(Error::Hash(format!("Unsupported hash scheme: ${}$", id)));
+    }
+
+    let parsed =
+        PasswordHash::new(hash).map_err(|e| Error::Hash(format!("Malformed PHC hash: {}", e)))?;
+    let scheme = parsed.algorithm.as_str().to_string();
+
+    let (outcome, weakness) = match scheme.as_str() {
+        "argon2id" | "argon2i" | "argon2d" => {
+            let params = argon2::Params::try_from(&parsed)
+                .map_err(|e| Error::Hash(format!("Invalid {} parameters: {}", scheme, e)))?;
+            check_argon2_cost(params.m_cost(), params.t_cost(), params.p_cost())?;
+            let weakness = if scheme != "argon2id" {
+                Some(format!("{} is superseded by argon2id", scheme))
+            } else if u64::from(params.m_cost()) * u64::from(params.t_cost())
+                < u64::from(ARGON2_MEMORY_KIB) * u64::from(ARGON2_ITERATIONS)
+            {
+                Some(format!(
+                    "m={} KiB, t={} is below the recommended m={} KiB, t={}",
+                    params.m_cost(),
+                    params.t_cost(),
+                    ARGON2_MEMORY_KIB,
+                    ARGON2_ITERATIONS
+                ))
+            } else {
+                None
+            };
+            (
+                argon2::Argon2::default().verify_password(password.as_bytes(), &parsed),
+                weakness,
+            )
+        }
+        "scrypt" => {
+            let params = scrypt::Params::try_from(&parsed)
+                .map_err(|e| Error::Hash(format!("Invalid scrypt parameters: {}", e)))?;
+            check_scrypt_cost(params.log_n(), params.r(), params.p())?;
+            let weakness =
+                (params.log_n() < SCRYPT_LOG_N || params.r() < SCRYPT_BLOCK_SIZE).then(|| {
+                    format!(
+                        "ln={}, r={} is below the recommended ln={}, r={}",
+                        params.log_n(),
+                        params.r(),
+                        SCRYPT_LOG_N,
+                        SCRYPT_BLOCK_SIZE
+                    )
+                });
+            (
+                scrypt::Scrypt.verify_password(password.as_bytes(), &parsed),
+                weakness,
+            )
+        }
+        "pbkdf2-sha256" | "pbkdf2-sha512" | "pbkdf2" => {
+            let params = pbkdf2::Params::try_from(&parsed)
+                .map_err(|e| Error::Hash(format!("Invalid {} parameters: {}", scheme, e)))?;
+            check_cost(
+                &format!("{} iterations", scheme),
+                params.rounds.into(),
+                PBKDF2_MAX_ITERATIONS.into(),
+            )?;
+            let recommended = match scheme.as_str() {
+                "pbkdf2-sha512" => PBKDF2_SHA512_ITERATIONS,
+                "pbkdf2-sha256" => PBKDF2_SHA256_ITERATIONS,
+                // Plain "pbkdf2" is PBKDF2-HMAC-SHA1, which needs even more.
+                _ => 1_300_000,
+            };
+            let weakness = (params.rounds < recommended).then(|| {
+                format!(
+                    "{} iterations is below the recommended {}",
+                    params.rounds, recommended
+                )
+            });
+            (
+                pbkdf2::Pbkdf2.verify_password(password.as_bytes(), &parsed),
+                weakness,
+            )
+        }
+        _ => unreachable!("checked against PHC_SCHEMES"),
+    };
+
+    match outcome {
+        Ok(()) => Ok(Verification::new(&scheme, true, weakness)),
+        Err(password_hash::Error::Password) => Ok(Verification::new(&scheme, false, weakness)),
+        Err(e) => Err(Error::Hash(format!(
+            "{} verification failed: {}",
+            scheme, e
+        ))),
+    }
+}
+
+fn verify_bcrypt(password: &str, hash: &str) -> Result<Verification> {
+    let parts: bcrypt::HashParts = hash
+        .parse()
+        .map_err(|e| Error::Hash(format!("Malformed bcrypt hash: {}", e)))?;
+    check_cost(
+        "bcrypt cost",
+        parts.get_cost().into(),
+        BCRYPT_MAX_COST.into(),
+    )?;
+    let matches = bcrypt::verify(password, hash)
+        .map_err(|e| Error::Hash(format!("bcrypt verification failed: {}", e)))?;
+    let weakness = (parts.get_cost() < BCRYPT_COST).then(|| {
+        format!(
+            "cost {} is below the recommended {}",
+            parts.get_cost(),
+            BCRYPT_COST
+        )
+    });
+    Ok(Verification::new("bcrypt", matches, weakness))
+}
+
+/// SHA-crypt rounds when the hash does not specify any.
+const SHA_CRYPT_DEFAULT_ROUNDS: u32 = 5000;
+
+fn verify_sha_crypt<D: Digest>(
+    password: &str,
+    rest: &str,
+    scheme: &str,
+    hash: &str,
+) -> Result<Verification> {
+    let (rounds, rest) = match rest.strip_prefix("rounds=") {
+        Some(rest) => {
+            let (rounds, rest) = rest
+                .split_once('$')
+                .ok_or_else(|| Error::Hash(format!("Malformed {} hash", scheme)))?;
+            let rounds: u32 = rounds
+                .parse()
+                .map_err(|_| Error::Hash(format!("Malformed {} rounds: {}", scheme, rounds)))?;
+            check_cost(
+                &format!("{} rounds", scheme),
+                rounds.into(),
+                SHA_CRYPT_MAX_ROUNDS.into(),
+            )?;
+            (Some(rounds.max(1000)), rest)
+        }
+        None => (None, rest),
+    };
+    let (salt, expected) = rest
+        .rsplit_once('$')
+        .ok_or_else(|| Error::Hash(format!("Malformed {} hash", scheme)))?;
+    let salt = &salt.as_bytes()[..salt.len().min(16)];
+
+    // Refuse to call a truncated or otherwise mangled hash a mismatch, and
+    // check before spending the rounds on it.
+    let digest_len = <D as Digest>::output_size();
+    let encoded_len = digest_len / 3 * 4 + [0, 2, 3][digest_len % 3];
+    if expected.len() != encoded_len || !expected.bytes().all(|b| CRYPT_BASE64.contains(&b)) {
+        return Err(Error::Hash(format!("Malformed {} hash: {}", scheme, hash)));
+    }
+
+    let digest = sha_crypt::<D>(
+        password.as_bytes(),
+        salt,
+        rounds.unwrap_or(SHA_CRYPT_DEFAULT_ROUNDS),
+    );
+    let encoded = crypt_base64(&sha_crypt_permute(&digest));
+    let matches = encoded.as_bytes().ct_eq(expected.as_bytes()).into();
+
+    Ok(Verification::new(
+        scheme,
+        matches,
+        Some(format!(
+            "{} is not memory-hard; rehash with argon2id",
+            scheme
+        )),
+    ))
+}
+
+/// The SHA-crypt algorithm (Drepper, 2007) shared by `$5$` and `$6$`.
+fn sha_crypt<D: Digest>(password: &[u8], salt: &[u8], rounds: u32) -> Vec<u8> {
+    let repeat_to =
+        |block: &[u8], len: usize| -> Vec<u8> { block.iter().copied().cycle().take(len).collect() };
+
+    let alternate = D::new()
+        .chain_update(password)
+        .chain_update(salt)
+        .chain_update(password)
+        .finalize();
+
+    let mut a = D::new().chain_update(password).chain_update(salt);
+    a.update(repeat_to(&alternate, password.len()));
+    let mut length = password.len();
+    while length > 0 {
+        if length & 1 == 1 {
+            a.update(&alternate);
+        } else {
+            a.update(password);
+        }
+        length >>= 1;
+    }
+    let mut c = a.finalize().to_vec();
+
+    let mut dp = D::new();
+    for _ in 0..password.len() {
+        dp.update(password);
+    }
+    let p_bytes = repeat_to(&dp.finalize(), password.len());
+
+    let mut ds = D::new();
+    for _ in 0..16 + usize::from(c[0]) {
+        ds.update(salt);
+    }
+    let s_bytes = repeat_to(&ds.finalize(), salt.len());
+
+    for round in 0..rounds {
+        let mut ctx = D::new();
+        if round % 2 == 1 {
+            ctx.update(&p_bytes);
+        } else {
+            ctx.update(&c);
+        }
+        if round % 3 != 0 {
+            ctx.update(&s_bytes);
+        }
+        if round % 7 != 0 {
+            ctx.update(&p_bytes);
+        }
+        if round % 2 == 1 {
+            ctx.update(&c);
+        } else {
+            ctx.update(&p_bytes);
+        }
+        c = ctx.finalize().to_vec();
+    }
+
+    c
+}
+
+/// Reorders a SHA-crypt digest into the byte order it is encoded in.
+fn sha_crypt_permute(digest: &[u8]) -> Vec<u8> {
+    let order: &[usize] = if digest.len() == 32 {
+        &[
+            0, 10, 20, 21, 1, 11, 12, 22, 2, 3, 13, 23, 24, 4, 14, 15, 25, 5, 6, 16, 26, 27, 7, 17,
+            18, 28, 8, 9, 19, 29, 31, 30,
+        ]
+    } else {
+        &[
+            0, 21, 42, 22, 43, 1, 44, 2, 23, 3, 24, 45, 25, 46, 4, 47, 5, 26, 6, 27, 48, 28, 49, 7,
+            50, 8, 29, 9, 30, 51, 31, 52, 10, 53, 11, 32, 12, 33, 54, 34, 55, 13, 56, 14, 35, 15,
+            36, 57, 37, 58, 16, 59, 17, 38, 18, 39, 60, 40, 61, 19, 62, 20, 41, 63,
+        ]
+    };
+    order.iter().map(|&i| digest[i]).collect()
+}
+
+const CRYPT_BASE64: &[u8] = b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// The crypt(3) base64 variant: `./0-9A-Za-z`, each group of three bytes
+/// (most significant first) emitted least significant six bits first.
+fn crypt_base64(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        // A trailing partial group is treated as the low bytes of a word.
+        let (word, chars) = match *chunk {
+            [b2, b1, b0] => (
+                (u32::from(b2) << 16) | (u32::from(b1) << 8) | u32::from(b0),
+                4,
+            ),
+            [b1, b0] => ((u32::from(b1) << 8) | u32::from(b0), 3),
+            [b0] => (u32::from(b0), 2),
+            _ => unreachable!(),
+        };
+        for k in 0..chars {
+            out.push(CRYPT_BASE64[((word >> (6 * k)) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+fn verify_ldap(password: &str, hash: &str) -> Result<Verification> {
+    let (scheme, encoded) = hash[1..]
+        .split_once('}')
+        .ok_or_else(|| Error::Hash("Malformed LDAP hash".to_string()))?;
+    let scheme = scheme.to_uppercase();
+    let decoded = general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|_| Error::Hash(format!("Malformed {{{}}} hash: invalid base64", scheme)))?;
+
+    let (digest_len, salted): (usize, bool) = match scheme.as_str() {
+        "SHA" => (20, false),
+        "SSHA" => (20, true),
+        "SHA256" => (32, false),
+        "SSHA256" => (32, true),
+        "SHA512" => (64, false),
+        "SSHA512" => (64, true),
+        _ => {
+            return Err(Error::Hash(format!(
+                "Unsupported LDAP hash scheme: {{{}}}",
+                scheme
+            )))
+        }
+    };
+    if decoded.len() < digest_len || (!salted && decoded.len() != digest_len) {
+        return Err(Error::Hash(format!(
+            "Malformed {{{}}} hash: wrong length",
+            scheme
+        )));
+    }
+    let (expected, salt) = decoded.split_at(digest_len);
+
+    let actual = match digest_len {
+        20 => Sha1::new()
+            .chain_update(password)
+            .chain_update(salt)
+            .finalize()
+            .to_vec(),
+        32 => Sha256::new()
+            .chain_update(password)
+            .chain_update(salt)
+            .finalize()
+            .to_vec(),
+        _ => Sha512::new()
+            .chain_update(password)
+            .chain_update(salt)
+            .finalize()
+            .to_vec(),
+    };
+    let matches = actual.ct_eq(expected).into();
+
+    let weakness = if salted {
+        "a single salted digest is fast to brute force; rehash with argon2id"
+    } else {
+        "unsalted digest; rehash with argon2id"
+    };
+    Ok(Verification::new(
+        &scheme.to_lowercase(),
+        matches,
+        Some(weakness.to_string()),
+    ))
+}
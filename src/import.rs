@@ -0,0 +1,481 @@
+use anyhow::Result;
+use passgen::secret::SecretString;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::vault::Entry;
+
+/// Export formats that can be imported into the vault.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    KeepassXml,
+    KeepassCsv,
+    Bitwarden,
+    OnePasswordCsv,
+    Pass,
+}
+
+impl ImportFormat {
+    pub const ALL: [ImportFormat; 5] = [
+        ImportFormat::KeepassXml,
+        ImportFormat::KeepassCsv,
+        ImportFormat::Bitwarden,
+        ImportFormat::OnePasswordCsv,
+        ImportFormat::Pass,
+    ];
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.name() == name.to_lowercase())
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|format| format.name()).collect();
+                anyhow::anyhow!(
+                    "Unsupported import format: {} (expected one of: {})",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ImportFormat::KeepassXml => "keepass-xml",
+            ImportFormat::KeepassCsv => "keepass-csv",
+            ImportFormat::Bitwarden => "bitwarden",
+            ImportFormat::OnePasswordCsv => "1password-csv",
+            ImportFormat::Pass => "pass",
+        }
+    }
+}
+
+/// An entry ready to be added to the vault, with the source fields that
+/// passgen's entry model had no place for.
+pub struct ImportedEntry {
+    pub entry: Entry,
+    pub dropped: Vec<String>,
+}
+
+pub struct ImportReport {
+    pub entries: Vec<ImportedEntry>,
+    /// Source items that were not imported at all, with the reason.
+    pub skipped: Vec<String>,
+}
+
+impl ImportReport {
+    fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            skipped: Vec::new(),
+        }
+    }
+}
+
+/// Fields collected from an export before they are mapped onto an `Entry`.
+#[derive(Default)]
+struct RawEntry {
+    name: String,
+    username: String,
+    password: String,
+    url: String,
+    notes: String,
+    folder: Vec<String>,
+    tags: Vec<String>,
+    dropped: Vec<String>,
+}
+
+impl RawEntry {
+    fn finish(self, report: &mut ImportReport) {
+        let name = if !self.name.trim().is_empty() {
+            self.name.trim().to_string()
+        } else if !self.url.is_empty() {
+            self.url.clone()
+        } else {
+            "Untitled".to_string()
+        };
+
+        let folder: Vec<&str> = self
+            .folder
+            .iter()
+            .map(|part| part.trim())
+            .filter(|part| !part.is_empty())
+            .collect();
+
+        let mut entry = Entry::new(&name, SecretString::new(self.password));
+        entry.username = self.username;
+        entry.url = self.url;
+        entry.notes = self.notes;
+        entry.folder = Some(folder.join("/")).filter(|folder| !folder.is_empty());
+        entry.tags = self
+            .tags
+            .into_iter()
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+
+        report.entries.push(ImportedEntry {
+            entry,
+            dropped: self.dropped,
+        });
+    }
+}
+
+/// Parses an export of the given format. `path` is a file for every format
+/// except `pass`, where it is the password-store directory.
+pub fn import(format: ImportFormat, path: &Path) -> Result<ImportReport> {
+    if format == ImportFormat::Pass {
+        return import_pass(path);
+    }
+
+    let contents = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    match format {
+        ImportFormat::KeepassXml => import_keepass_xml(&contents),
+        ImportFormat::KeepassCsv => import_csv(&contents, true),
+        ImportFormat::OnePasswordCsv => import_csv(&contents, false),
+        ImportFormat::Bitwarden => import_bitwarden(&contents),
+        ImportFormat::Pass => unreachable!(),
+    }
+}
+
+/// Returns `$PASSWORD_STORE_DIR`, or `~/.password-store`.
+pub fn default_pass_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("PASSWORD_STORE_DIR") {
+        return PathBuf::from(dir);
+    }
+    PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string())).join(".password-store")
+}
+
+/// Parses a KeePass 2 XML export. The top-level group is the database
+/// itself, so only the groups below it become the folder path. Entry history
+/// is ignored.
+fn import_keepass_xml(contents: &str) -> Result<ImportReport> {
+    let mut report = ImportReport::new();
+    let mut reader = Reader::from_str(contents);
+
+    let mut elements: Vec<String> = Vec::new();
+    let mut groups: Vec<String> = Vec::new();
+    let mut entry: Option<RawEntry> = None;
+    let mut text = String::new();
+    let mut key = String::new();
+    let mut value = String::new();
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| anyhow::anyhow!("Malformed KeePass XML: {}", e))?;
+        let in_history = elements.iter().any(|element| element == "History");
+
+        match event {
+            Event::Start(start) => {
+                let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+                match name.as_str() {
+                    "Group" => groups.push(String::new()),
+                    "Entry" if !in_history => entry = Some(RawEntry::default()),
+                    "String" => {
+                        key.clear();
+                        value.clear();
+                    }
+                    _ => {}
+                }
+                elements.push(name);
+                text.clear();
+            }
+            Event::Text(t) => text.push_str(
+                &t.unescape()
+                    .map_err(|e| anyhow::anyhow!("Malformed KeePass XML: {}", e))?,
+            ),
+            Event::CData(t) => text.push_str(&String::from_utf8_lossy(&t)),
+            Event::End(_) => {
+                let name = elements.pop().unwrap_or_default();
+                let parent = elements.last().map(String::as_str);
+
+                match (name.as_str(), parent) {
+                    ("Name", Some("Group")) => {
+                        if let Some(group) = groups.last_mut() {
+                            *group = text.clone();
+                        }
+                    }
+                    ("Key", Some("String")) => key = text.clone(),
+                    ("Value", Some("String")) => value = text.clone(),
+                    ("String", Some("Entry")) if !in_history => {
+                        if let Some(raw) = entry.as_mut() {
+                            match key.as_str() {
+                                "Title" => raw.name = value.clone(),
+                                "UserName" => raw.username = value.clone(),
+                                "Password" => raw.password = value.clone(),
+                                "URL" => raw.url = value.clone(),
+                                "Notes" => raw.notes = value.clone(),
+                                _ if !value.is_empty() => {
+                                    raw.dropped.push(format!("custom field \"{}\"", key))
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    ("Tags", Some("Entry")) if !in_history => {
+                        if let Some(raw) = entry.as_mut() {
+                            raw.tags = text.split([';', ',']).map(str::to_string).collect();
+                        }
+                    }
+                    ("Binary", Some("Entry")) if !in_history => {
+                        if let Some(raw) = entry.as_mut() {
+                            raw.dropped.push("attachment".to_string());
+                        }
+                    }
+                    ("Entry", _) if !in_history => {
+                        if let Some(mut raw) = entry.take() {
+                            raw.folder = groups.iter().skip(1).cloned().collect();
+                            raw.finish(&mut report);
+                        }
+                    }
+                    ("Group", _) => {
+                        groups.pop();
+                    }
+                    _ => {}
+                }
+                text.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(report)
+}
+
+/// Parses a CSV export by matching column headers against known names.
+/// KeePass CSV group paths start with the database root, which is dropped
+/// to match the XML importer.
+fn import_csv(contents: &str, keepass: bool) -> Result<ImportReport> {
+    let mut report = ImportReport::new();
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(contents.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| anyhow::anyhow!("Malformed CSV: {}", e))?
+        .iter()
+        .map(|header| header.trim().to_string())
+        .collect();
+
+    for (row, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| anyhow::anyhow!("Malformed CSV: {}", e))?;
+        let mut raw = RawEntry::default();
+
+        for (header, value) in headers.iter().zip(record.iter()) {
+            match header.to_lowercase().as_str() {
+                "title" | "account" | "name" => raw.name = value.to_string(),
+                "username" | "login name" | "login" | "user" => raw.username = value.to_string(),
+                "password" => raw.password = value.to_string(),
+                "url" | "web site" | "website" | "login_uri" => raw.url = value.to_string(),
+                "notes" | "comments" | "note" => raw.notes = value.to_string(),
+                "group" | "folder" => {
+                    let mut parts: Vec<String> = value.split('/').map(str::to_string).collect();
+                    if keepass && !parts.is_empty() {
+                        parts.remove(0);
+                    }
+                    raw.folder = parts;
+                }
+                "tags" => raw.tags = value.split([';', ',']).map(str::to_string).collect(),
+                // Display metadata with no bearing on the credential itself
+                "icon" | "favorite" | "archived" | "last modified" | "created" => {}
+                _ if !value.trim().is_empty() => raw.dropped.push(format!("column \"{}\"", header)),
+                _ => {}
+            }
+        }
+
+        if raw.name.is_empty() && raw.username.is_empty() && raw.password.is_empty() {
+            report
+                .skipped
+                .push(format!("row {}: empty record", row + 2));
+            continue;
+        }
+        raw.finish(&mut report);
+    }
+
+    Ok(report)
+}
+
+/// Parses an unencrypted Bitwarden JSON export. Logins and secure notes are
+/// imported; cards and identities have no equivalent and are skipped.
+fn import_bitwarden(contents: &str) -> Result<ImportReport> {
+    let mut report = ImportReport::new();
+    let export: Value = serde_json::from_str(contents)
+        .map_err(|e| anyhow::anyhow!("Malformed Bitwarden export: {}", e))?;
+
+    if export["encrypted"].as_bool().unwrap_or(false) {
+        return Err(anyhow::anyhow!(
+            "Encrypted Bitwarden exports are not supported; export as unencrypted JSON"
+        ));
+    }
+
+    let folders: HashMap<&str, &str> = export["folders"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|folder| Some((folder["id"].as_str()?, folder["name"].as_str()?)))
+        .collect();
+    let text = |value: &Value| value.as_str().unwrap_or_default().to_string();
+
+    for item in export["items"].as_array().into_iter().flatten() {
+        let name = text(&item["name"]);
+        match item["type"].as_u64() {
+            Some(1) | Some(2) => {}
+            Some(3) => {
+                report
+                    .skipped
+                    .push(format!("{}: card items are not supported", name));
+                continue;
+            }
+            Some(4) => {
+                report
+                    .skipped
+                    .push(format!("{}: identity items are not supported", name));
+                continue;
+            }
+            _ => {
+                report.skipped.push(format!("{}: unknown item type", name));
+                continue;
+            }
+        }
+
+        let mut raw = RawEntry {
+            name,
+            notes: text(&item["notes"]),
+            ..Default::default()
+        };
+        if let Some(folder) = item["folderId"].as_str().and_then(|id| folders.get(id)) {
+            raw.folder = folder.split('/').map(str::to_string).collect();
+        }
+
+        let login = &item["login"];
+        raw.username = text(&login["username"]);
+        raw.password = text(&login["password"]);
+        let uris: Vec<String> = login["uris"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|uri| text(&uri["uri"]))
+            .filter(|uri| !uri.is_empty())
+            .collect();
+        if let Some(first) = uris.first() {
+            raw.url = first.clone();
+        }
+        if uris.len() > 1 {
+            raw.dropped
+                .push(format!("{} additional URIs", uris.len() - 1));
+        }
+        if !text(&login["totp"]).is_empty() {
+            raw.dropped.push("TOTP secret".to_string());
+        }
+        for field in item["fields"].as_array().into_iter().flatten() {
+            raw.dropped
+                .push(format!("custom field \"{}\"", text(&field["name"])));
+        }
+        if item["passwordHistory"]
+            .as_array()
+            .is_some_and(|history| !history.is_empty())
+        {
+            raw.dropped.push("password history".to_string());
+        }
+
+        raw.finish(&mut report);
+    }
+
+    Ok(report)
+}
+
+/// Imports a `pass` password store by decrypting each `.gpg` file with the
+/// user's `gpg`. The first line is the password; `key: value` lines for
+/// known keys fill the matching fields and everything else becomes notes.
+fn import_pass(dir: &Path) -> Result<ImportReport> {
+    if !dir.is_dir() {
+        return Err(anyhow::anyhow!(
+            "Password store not found: {}",
+            dir.display()
+        ));
+    }
+
+    let mut files = Vec::new();
+    collect_gpg_files(dir, &mut files)?;
+    files.sort();
+
+    let mut report = ImportReport::new();
+    for file in files {
+        let relative = file.strip_prefix(dir).unwrap_or(&file).with_extension("");
+        let display = relative.display().to_string();
+
+        let output = Command::new("gpg")
+            .args(["--quiet", "--batch", "--decrypt"])
+            .arg(&file)
+            .output()
+            .map_err(|e| anyhow::anyhow!("Failed to run gpg: {}", e))?;
+        if !output.status.success() {
+            report
+                .skipped
+                .push(format!("{}: gpg could not decrypt it", display));
+            continue;
+        }
+
+        let contents = String::from_utf8_lossy(&output.stdout);
+        let mut lines = contents.lines();
+        let mut raw = RawEntry {
+            password: lines.next().unwrap_or_default().to_string(),
+            ..Default::default()
+        };
+
+        let mut components: Vec<String> = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        raw.name = components.pop().unwrap_or_default();
+        raw.folder = components;
+
+        let mut notes = Vec::new();
+        for line in lines {
+            if line.starts_with("otpauth://") {
+                raw.dropped.push("TOTP secret".to_string());
+                continue;
+            }
+            match line.split_once(':') {
+                Some((key, value)) => match key.trim().to_lowercase().as_str() {
+                    "login" | "username" | "user" if raw.username.is_empty() => {
+                        raw.username = value.trim().to_string()
+                    }
+                    "url" | "website" if raw.url.is_empty() => raw.url = value.trim().to_string(),
+                    _ => notes.push(line),
+                },
+                None => notes.push(line),
+            }
+        }
+        raw.notes = notes.join("\n").trim().to_string();
+
+        raw.finish(&mut report);
+    }
+
+    Ok(report)
+}
+
+fn collect_gpg_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for item in fs::read_dir(dir)? {
+        let path = item?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            collect_gpg_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "gpg") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! Password and passphrase generation, strength analysis and password
+//! hashing, as used by the `passgen` CLI and the `passgen-web` server.
+//!
+//! ```no_run
+//! use passgen::{PasswordGenerator, Result};
+//!
+//! fn main() -> Result<()> {
+//!     let mut generator = PasswordGenerator::new();
+//!     generator.include_all().exclude_ambiguous().require_each_class();
+//!     let password = generator.generate(20)?;
+//!     println!("{:.1} bits", generator.entropy(20)?);
+//!     store(password.expose());
+//!     Ok(())
+//! }
+//! # fn store(_: &str) {}
+//! ```
+//!
+//! The items re-exported at the crate root are the stable API. The modules
+//! are public as well, for the binaries and for finer-grained access, but
+//! may change between minor versions.
+
+pub mod breach;
+pub mod encoding;
+pub mod error;
+pub mod generator;
+pub mod hashing;
+pub mod pattern;
+pub mod policy;
+pub mod regex;
+pub mod report;
+pub mod rules;
+pub mod secret;
+pub mod strength;
+pub mod utils;
+pub mod wordlist;
+
+pub use breach::breach_count;
+pub use error::{Error, Result};
+pub use generator::{Batch, CharClass, CharsetPreset, PasswordGenerator};
+pub use hashing::{hash_password, verify_password, HashAlgorithm, HashParams, Verification};
+pub use pattern::Pattern;
+pub use policy::Policy;
+pub use regex::Regex;
+pub use rules::PasswordRules;
+pub use secret::SecretString;
+pub use strength::{estimate_strength, StrengthEstimate};
+pub use utils::{
+    analyze_password, analyze_with_estimate, calculate_entropy, check_password_strength,
+    format_password, generate_hash, generate_passphrase, generate_passphrases, generate_token,
+    passphrase_entropy, token_entropy, PasswordStrength,
+};
+pub use wordlist::Wordlist;
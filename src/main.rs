@@ -1,11 +1,33 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use colored::*;
+use std::path::PathBuf;
+use std::time::Duration;
 
-mod generator;
-mod utils;
+mod clipboard;
+mod config;
+mod import;
+mod output;
+mod vault;
 
-use generator::PasswordGenerator;
-use utils::*;
+use clipboard::{ClearOutcome, CopiedSecret};
+use config::{Config, Profile};
+use import::{ImportFormat, ImportReport};
+use output::OutputFormat;
+use passgen::breach;
+use passgen::generator::{CharClass, CharsetPreset, PasswordGenerator};
+use passgen::hashing::{verify_password, HashParams};
+use passgen::pattern::Pattern;
+use passgen::policy::Policy;
+use passgen::regex::Regex;
+use passgen::report::{
+    CheckResponse, GenerateResponse, HashResponse, PassphraseResponse, TokenResponse,
+    VerifyResponse,
+};
+use passgen::rules::PasswordRules;
+use passgen::secret::SecretString;
+use passgen::utils::*;
+use passgen::wordlist::Wordlist;
+use vault::{Entry, Vault};
 
 #[derive(Parser)]
 #[command(name = "passgen")]
@@ -13,144 +35,1377 @@ use utils::*;
 #[command(version)]
 #[command(propagate_version = true)]
 struct Cli {
+    /// Output format for generate, derive, passphrase, token, check, hash and verify: plain, json, yaml
+    #[arg(long, global = true, default_value = "plain")]
+    output: String,
+
+    /// Profile from the config file to take generate, derive and passphrase defaults from
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Character set and policy options shared by every generation mode
+#[derive(Args)]
+struct CharsetOptions {
+    /// Include uppercase letters
+    #[arg(short, long)]
+    uppercase: bool,
+
+    /// Include lowercase letters
+    #[arg(long)]
+    lowercase: bool,
+
+    /// Include numbers
+    #[arg(short, long)]
+    numbers: bool,
+
+    /// Include special characters
+    #[arg(short, long)]
+    special: bool,
+
+    /// Named character sets to include (alphanumeric, shell-safe, url-safe, xml-safe, hex, base58)
+    #[arg(long, value_delimiter = ',')]
+    charset: Vec<String>,
+
+    /// Additional characters to include
+    #[arg(long)]
+    include_chars: Option<String>,
+
+    /// Exclude similar characters (l, 1, I, O, 0)
+    #[arg(short, long, overrides_with = "no_exclude_similar")]
+    exclude_similar: bool,
+
+    /// Keep similar characters even if the profile excludes them
+    #[arg(long, overrides_with = "exclude_similar")]
+    no_exclude_similar: bool,
+
+    /// Exclude ambiguous characters ({}, [], (), /, \, ', ", ~, ;, :, ., >, <)
+    #[arg(long, overrides_with = "no_exclude_ambiguous")]
+    exclude_ambiguous: bool,
+
+    /// Keep ambiguous characters even if the profile excludes them
+    #[arg(long, overrides_with = "exclude_ambiguous")]
+    no_exclude_ambiguous: bool,
+
+    /// Require at least one character from every selected character set
+    #[arg(long, overrides_with = "no_require_each")]
+    require_each: bool,
+
+    /// Don't require every character set even if the profile does
+    #[arg(long, overrides_with = "require_each")]
+    no_require_each: bool,
+
+    /// Minimum number of uppercase letters
+    #[arg(long)]
+    min_uppercase: Option<usize>,
+
+    /// Minimum number of lowercase letters
+    #[arg(long)]
+    min_lowercase: Option<usize>,
+
+    /// Minimum number of numbers
+    #[arg(long)]
+    min_numbers: Option<usize>,
+
+    /// Minimum number of special characters
+    #[arg(long)]
+    min_special: Option<usize>,
+
+    /// Maximum number of uppercase letters
+    #[arg(long)]
+    max_uppercase: Option<usize>,
+
+    /// Maximum number of lowercase letters
+    #[arg(long)]
+    max_lowercase: Option<usize>,
+
+    /// Maximum number of numbers
+    #[arg(long)]
+    max_numbers: Option<usize>,
+
+    /// Maximum number of special characters
+    #[arg(long)]
+    max_special: Option<usize>,
+}
+
+impl CharsetOptions {
+    /// Fills in options not given on the command line from a profile. Flags
+    /// that pick characters replace the profile's character set as a whole.
+    fn apply_profile(&mut self, profile: &Profile) {
+        let selects_charset = self.uppercase
+            || self.lowercase
+            || self.numbers
+            || self.special
+            || !self.charset.is_empty()
+            || self.include_chars.is_some();
+        if !selects_charset {
+            self.uppercase = profile.uppercase.unwrap_or(false);
+            self.lowercase = profile.lowercase.unwrap_or(false);
+            self.numbers = profile.numbers.unwrap_or(false);
+            self.special = profile.special.unwrap_or(false);
+            self.charset = profile.charset.clone().unwrap_or_default();
+            self.include_chars = profile.include_chars.clone();
+        }
+
+        self.exclude_similar = flag_or_profile(
+            self.exclude_similar,
+            self.no_exclude_similar,
+            profile.exclude_similar,
+        );
+        self.exclude_ambiguous = flag_or_profile(
+            self.exclude_ambiguous,
+            self.no_exclude_ambiguous,
+            profile.exclude_ambiguous,
+        );
+        self.require_each = flag_or_profile(
+            self.require_each,
+            self.no_require_each,
+            profile.require_each,
+        );
+        self.min_uppercase = self.min_uppercase.or(profile.min_uppercase);
+        self.min_lowercase = self.min_lowercase.or(profile.min_lowercase);
+        self.min_numbers = self.min_numbers.or(profile.min_numbers);
+        self.min_special = self.min_special.or(profile.min_special);
+        self.max_uppercase = self.max_uppercase.or(profile.max_uppercase);
+        self.max_lowercase = self.max_lowercase.or(profile.max_lowercase);
+        self.max_numbers = self.max_numbers.or(profile.max_numbers);
+        self.max_special = self.max_special.or(profile.max_special);
+    }
+
+    fn build(&self) -> anyhow::Result<PasswordGenerator> {
+        let mut generator = PasswordGenerator::new();
+
+        // Set character sets based on flags
+        if self.uppercase {
+            generator.include_uppercase();
+        }
+        if self.lowercase {
+            generator.include_lowercase();
+        }
+        if self.numbers {
+            generator.include_numbers();
+        }
+        if self.special {
+            generator.include_special();
+        }
+        for name in &self.charset {
+            generator.with_charset(CharsetPreset::from_name(name)?);
+        }
+        if let Some(chars) = &self.include_chars {
+            generator.include_chars(chars);
+        }
+        if self.exclude_similar {
+            generator.exclude_similar();
+        }
+        if self.exclude_ambiguous {
+            generator.exclude_ambiguous();
+        }
+
+        // If no character sets specified, use all
+        if !(self.uppercase
+            || self.lowercase
+            || self.numbers
+            || self.special
+            || !self.charset.is_empty()
+            || self.include_chars.is_some())
+        {
+            generator.include_all();
+        }
+
+        // Apply per-class policy limits
+        if self.require_each {
+            generator.require_each_class();
+        }
+        let limits = [
+            (CharClass::Uppercase, self.min_uppercase, self.max_uppercase),
+            (CharClass::Lowercase, self.min_lowercase, self.max_lowercase),
+            (CharClass::Number, self.min_numbers, self.max_numbers),
+            (CharClass::Special, self.min_special, self.max_special),
+        ];
+        for (class, min, max) in limits {
+            if let Some(min) = min {
+                generator.min_count(class, min);
+            }
+            if let Some(max) = max {
+                generator.max_count(class, max);
+            }
+        }
+
+        Ok(generator)
+    }
+}
+
+/// Options for generating many secrets in one run
+#[derive(Args)]
+struct BatchOptions {
+    /// Generate this many distinct secrets, one per line
+    #[arg(long)]
+    count: Option<usize>,
+
+    /// File of account names, one per line; generates one secret per name as CSV
+    #[arg(long, value_name = "FILE")]
+    labels: Option<PathBuf>,
+
+    /// Write CSV with a header row (and a label column when --labels is given)
+    #[arg(long)]
+    csv: bool,
+
+    /// Write the secrets to this file instead of stdout
+    #[arg(long, value_name = "FILE")]
+    out: Option<PathBuf>,
+}
+
+impl BatchOptions {
+    fn is_batch(&self) -> bool {
+        self.count.is_some() || self.labels.is_some() || self.csv || self.out.is_some()
+    }
+
+    /// Reads the label file, if any, skipping blank lines.
+    fn read_labels(&self) -> anyhow::Result<Option<Vec<String>>> {
+        let Some(path) = &self.labels else {
+            return Ok(None);
+        };
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        Ok(Some(
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+        ))
+    }
+
+    /// The batch size: `--count`, or one per label.
+    fn size(&self, labels: Option<&[String]>) -> anyhow::Result<usize> {
+        match (self.count, labels) {
+            (Some(count), Some(labels)) if count != labels.len() => Err(anyhow::anyhow!(
+                "--count {} does not match the {} labels given",
+                count,
+                labels.len()
+            )),
+            (_, Some(labels)) => Ok(labels.len()),
+            (Some(count), None) => Ok(count),
+            (None, None) => Ok(1),
+        }
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Generate a random password
     Generate {
-        /// Length of the password (default: 16)
-        #[arg(short, long, default_value = "16")]
-        length: usize,
-
-        /// Include uppercase letters
+        /// Length of the password (default: 16, or the nearest length --rules allow)
         #[arg(short, long)]
-        uppercase: bool,
+        length: Option<usize>,
 
-        /// Include lowercase letters
-        #[arg(short, long)]
-        lowercase: bool,
+        #[command(flatten)]
+        charset: CharsetOptions,
 
-        /// Include numbers
-        #[arg(short, long)]
-        numbers: bool,
+        /// Build the password from consonant-vowel syllables that are easy to read aloud
+        #[arg(long)]
+        pronounceable: bool,
 
-        /// Include special characters
-        #[arg(short, long)]
-        special: bool,
+        /// Number of digits to mix into a pronounceable password
+        #[arg(long, default_value = "0", requires = "pronounceable")]
+        digits: usize,
+
+        /// Number of symbols to mix into a pronounceable password
+        #[arg(long, default_value = "0", requires = "pronounceable")]
+        symbols: usize,
+
+        /// Generate a password matching a template, e.g. 'Cvccvc-99-Cvccvc' or '[A-Z]{4}-\d{4}'
+        #[arg(long, conflicts_with_all = ["length", "pronounceable"])]
+        pattern: Option<String>,
 
-        /// Exclude similar characters (l, 1, I, O, 0)
+        /// Generate a password fully matching a bounded regex, e.g. '[a-z]{4,8}[0-9]{2}'
+        #[arg(long, conflicts_with_all = ["length", "pronounceable", "pattern"])]
+        regex: Option<String>,
+
+        /// Follow a site's passwordrules, e.g. 'required: upper; required: digit; minlength: 20'
+        #[arg(long, conflicts_with_all = ["pronounceable", "pattern", "regex"])]
+        rules: Option<String>,
+
+        /// Output format: plain, base64, base64url, hex, base32, crockford, base58, z85,
+        /// ascii85, percent, shell, json, yaml, xml
         #[arg(short, long)]
-        exclude_similar: bool,
+        format: Option<String>,
+
+        /// Copy password to clipboard
+        #[arg(short, long, overrides_with = "no_copy")]
+        copy: bool,
+
+        /// Don't copy to the clipboard even if the profile does
+        #[arg(long, overrides_with = "copy")]
+        no_copy: bool,
 
-        /// Exclude ambiguous characters ({}, [], (), /, \, ', ", ~, ;, :, ., >, <)
+        /// Restore or clear the clipboard after this many seconds if it still holds the secret
+        #[arg(long, value_name = "SECS")]
+        clear_after: Option<u64>,
+
+        #[command(flatten)]
+        batch: BatchOptions,
+    },
+
+    /// Derive a reproducible password for a site from a master password
+    Derive {
+        /// Site the password is for (e.g. example.com)
+        site: String,
+
+        /// Account username on the site
+        #[arg(long, default_value = "")]
+        username: String,
+
+        /// Counter to bump when the site password needs rotating
+        #[arg(long, default_value = "1")]
+        counter: u32,
+
+        /// Length of the password (default: 16)
         #[arg(short, long)]
-        exclude_ambiguous: bool,
+        length: Option<usize>,
 
-        /// Output format: plain, base64, hex
-        #[arg(short, long, default_value = "plain")]
-        format: String,
+        #[command(flatten)]
+        charset: CharsetOptions,
 
-        /// Copy password to clipboard (macOS only)
+        /// Output format: plain, base64, base64url, hex, base32, crockford, base58, z85,
+        /// ascii85, percent, shell, json, yaml, xml
         #[arg(short, long)]
+        format: Option<String>,
+
+        /// Copy password to clipboard
+        #[arg(short, long, overrides_with = "no_copy")]
         copy: bool,
+
+        /// Don't copy to the clipboard even if the profile does
+        #[arg(long, overrides_with = "copy")]
+        no_copy: bool,
+
+        /// Restore or clear the clipboard after this many seconds if it still holds the secret
+        #[arg(long, value_name = "SECS")]
+        clear_after: Option<u64>,
+    },
+
+    /// Manage the encrypted password vault
+    Vault {
+        /// Vault file (default: $PASSGEN_VAULT or ~/.local/share/passgen/vault.json)
+        #[arg(long, global = true)]
+        vault: Option<PathBuf>,
+
+        #[command(subcommand)]
+        command: VaultCommand,
     },
 
     /// Generate a passphrase
     Passphrase {
         /// Number of words in the passphrase (default: 4)
-        #[arg(short, long, default_value = "4")]
-        words: usize,
+        #[arg(short, long)]
+        words: Option<usize>,
 
         /// Separator between words (default: space)
-        #[arg(short, long, default_value = " ")]
-        separator: String,
+        #[arg(short, long)]
+        separator: Option<String>,
 
         /// Include numbers in the passphrase
-        #[arg(short, long)]
+        #[arg(short, long, overrides_with = "no_numbers")]
         numbers: bool,
 
+        /// Leave out numbers even if the profile includes them
+        #[arg(long, overrides_with = "numbers")]
+        no_numbers: bool,
+
         /// Include special characters in the passphrase
-        #[arg(short, long)]
+        #[arg(long, overrides_with = "no_special")]
         special: bool,
 
-        /// Copy passphrase to clipboard (macOS only)
+        /// Leave out special characters even if the profile includes them
+        #[arg(long, overrides_with = "special")]
+        no_special: bool,
+
+        /// Wordlist: a built-in name (common, eff-large, eff-short) or a file with one word per line or diceware `11111 word` lines
+        #[arg(long, env = "PASSGEN_WORDLIST")]
+        wordlist: Option<String>,
+
+        /// Pick words from physical dice rolls typed in instead of the system RNG
+        #[arg(
+            long,
+            conflicts_with_all = ["numbers", "special", "count", "labels", "csv", "out"]
+        )]
+        dice: bool,
+
+        /// Copy passphrase to clipboard
+        #[arg(short, long, overrides_with = "no_copy")]
+        copy: bool,
+
+        /// Don't copy to the clipboard even if the profile does
+        #[arg(long, overrides_with = "copy")]
+        no_copy: bool,
+
+        /// Restore or clear the clipboard after this many seconds if it still holds the secret
+        #[arg(long, value_name = "SECS")]
+        clear_after: Option<u64>,
+
+        #[command(flatten)]
+        batch: BatchOptions,
+    },
+
+    /// Generate a token of raw random bytes, for API keys and secrets
+    Token {
+        /// Number of random bytes; the token has exactly 8 bits of strength per byte
+        #[arg(short, long, default_value = "32")]
+        bytes: usize,
+
+        /// Encoding: base64, base64url, hex, base32, crockford, base58, z85, ascii85, percent
+        #[arg(short, long, default_value = "base64url")]
+        encoding: String,
+
+        /// Copy token to clipboard
         #[arg(short, long)]
         copy: bool,
+
+        /// Restore or clear the clipboard after this many seconds if it still holds the secret
+        #[arg(long, value_name = "SECS")]
+        clear_after: Option<u64>,
     },
 
     /// Check password strength
     Check {
-        /// Password to check
-        password: String,
+        /// Password to check; prompted for without echo if omitted. Passing it
+        /// here leaves it in shell history and the process list
+        #[arg(conflicts_with_all = ["stdin", "batch"])]
+        password: Option<String>,
+
+        /// Read the password from the first line of stdin
+        #[arg(long)]
+        stdin: bool,
+
+        /// Check every line of stdin as a separate password
+        #[arg(long, conflicts_with = "stdin")]
+        batch: bool,
+
+        /// Print the password in the results instead of masking it
+        #[arg(long)]
+        show: bool,
+
+        /// Local Have I Been Pwned SHA-1 file (ordered by hash) to look the password up in
+        #[arg(long, env = "PASSGEN_BREACH_DB")]
+        breach_db: Option<PathBuf>,
+
+        /// Also check the password against a site's passwordrules; exits with status 1 if it breaks any
+        #[arg(long)]
+        rules: Option<String>,
+
+        /// Also check the password against a compliance policy: nist-800-63b, pci-dss-4,
+        /// owasp-asvs-l2, cis (repeatable); exits with status 1 if it fails any
+        #[arg(long)]
+        policy: Vec<String>,
     },
 
-    /// Generate a secure hash
+    /// Hash a password for storage, or compute a digest
     Hash {
-        /// Input string to hash
-        input: String,
+        /// Input string to hash; prompted for without echo if omitted
+        #[arg(conflicts_with = "stdin")]
+        input: Option<String>,
+
+        /// Read the input from the first line of stdin
+        #[arg(long)]
+        stdin: bool,
 
-        /// Hash algorithm: sha256, sha512, base64
+        /// Print the input alongside the hash instead of masking it
+        #[arg(long)]
+        show: bool,
+
+        /// Password hashing: argon2id, bcrypt, scrypt, pbkdf2-sha256, pbkdf2-sha512.
+        /// Unsalted digests: sha256, sha512. Encoding (not a hash): base64
         #[arg(short, long, default_value = "sha256")]
         algorithm: String,
+
+        /// Argon2 memory cost in KiB
+        #[arg(long)]
+        memory: Option<u32>,
+
+        /// Argon2 passes or PBKDF2 iterations
+        #[arg(long)]
+        iterations: Option<u32>,
+
+        /// Argon2 lanes or scrypt parallelism
+        #[arg(long)]
+        parallelism: Option<u32>,
+
+        /// bcrypt work factor or scrypt log2(N)
+        #[arg(long)]
+        cost: Option<u32>,
+    },
+
+    /// Check a password against a stored hash
+    Verify {
+        /// Stored hash (PHC argon2/scrypt/pbkdf2, $2b$ bcrypt, $5$/$6$ sha-crypt, {SSHA} LDAP)
+        hash: String,
+
+        /// Candidate password; prompted for without echo if omitted. Passing it
+        /// here leaves it in shell history and the process list
+        #[arg(conflicts_with = "stdin")]
+        password: Option<String>,
+
+        /// Read the password from the first line of stdin
+        #[arg(long)]
+        stdin: bool,
     },
 }
 
+#[derive(Subcommand)]
+enum VaultCommand {
+    /// Create a new empty vault
+    Init,
+
+    /// Add an entry to the vault
+    Add {
+        /// Entry name
+        name: String,
+
+        /// Username for the entry
+        #[arg(long, default_value = "")]
+        username: String,
+
+        /// URL for the entry
+        #[arg(long, default_value = "")]
+        url: String,
+
+        /// Free-form notes
+        #[arg(long, default_value = "")]
+        notes: String,
+
+        /// Folder to file the entry under
+        #[arg(long)]
+        folder: Option<String>,
+
+        /// Tag to attach (repeatable)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+
+        /// Generate the password instead of prompting for it
+        #[arg(short, long)]
+        generate: bool,
+
+        /// Length of a generated password (default: 20)
+        #[arg(short, long, default_value = "20")]
+        length: usize,
+
+        #[command(flatten)]
+        charset: CharsetOptions,
+    },
+
+    /// Show an entry
+    Get {
+        /// Entry name
+        name: String,
+
+        /// Print the password instead of masking it
+        #[arg(long)]
+        show: bool,
+
+        /// Copy the password to clipboard
+        #[arg(short, long)]
+        copy: bool,
+
+        /// Restore or clear the clipboard after this many seconds if it still holds the secret
+        #[arg(long, value_name = "SECS", requires = "copy")]
+        clear_after: Option<u64>,
+    },
+
+    /// List entries
+    List {
+        /// Only list entries in this folder
+        #[arg(long)]
+        folder: Option<String>,
+
+        /// Only list entries with this tag
+        #[arg(long)]
+        tag: Option<String>,
+    },
+
+    /// Remove an entry
+    Rm {
+        /// Entry name
+        name: String,
+    },
+
+    /// Change fields of an entry
+    Edit {
+        /// Entry name
+        name: String,
+
+        /// New username
+        #[arg(long)]
+        username: Option<String>,
+
+        /// New URL
+        #[arg(long)]
+        url: Option<String>,
+
+        /// New notes
+        #[arg(long)]
+        notes: Option<String>,
+
+        /// New folder
+        #[arg(long)]
+        folder: Option<String>,
+
+        /// Replace the tags (repeatable)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+
+        /// Prompt for a new password
+        #[arg(long)]
+        password: bool,
+
+        /// Replace the password with a generated one
+        #[arg(short, long, conflicts_with = "password")]
+        generate: bool,
+
+        /// Length of a generated password (default: 20)
+        #[arg(short, long, default_value = "20")]
+        length: usize,
+
+        #[command(flatten)]
+        charset: CharsetOptions,
+    },
+
+    /// Import entries from another password manager's export
+    Import {
+        /// Export format: keepass-xml, keepass-csv, bitwarden, 1password-csv, pass
+        format: String,
+
+        /// Export file, or the store directory for pass (default: ~/.password-store)
+        path: Option<PathBuf>,
+
+        /// Report what would be imported without touching the vault
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+impl Commands {
+    /// Fills in options not given on the command line from a profile.
+    fn apply_profile(&mut self, profile: &Profile) -> anyhow::Result<()> {
+        match self {
+            Commands::Generate {
+                length,
+                charset,
+                pronounceable,
+                pattern,
+                regex,
+                rules,
+                format,
+                copy,
+                no_copy,
+                clear_after,
+                ..
+            } => {
+                charset.apply_profile(profile);
+                *length = length.or(profile.length);
+                if !*pronounceable && pattern.is_none() && regex.is_none() && rules.is_none() {
+                    rules.clone_from(&profile.rules);
+                }
+                if format.is_none() {
+                    format.clone_from(&profile.format);
+                }
+                *copy = flag_or_profile(*copy, *no_copy, profile.copy);
+                *clear_after = clear_after.or(profile.clear_after);
+            }
+            Commands::Derive {
+                length,
+                charset,
+                format,
+                copy,
+                no_copy,
+                clear_after,
+                ..
+            } => {
+                charset.apply_profile(profile);
+                *length = length.or(profile.length);
+                if format.is_none() {
+                    format.clone_from(&profile.format);
+                }
+                *copy = flag_or_profile(*copy, *no_copy, profile.copy);
+                *clear_after = clear_after.or(profile.clear_after);
+            }
+            Commands::Passphrase {
+                words,
+                separator,
+                numbers,
+                no_numbers,
+                special,
+                no_special,
+                wordlist,
+                dice,
+                copy,
+                no_copy,
+                clear_after,
+                ..
+            } => {
+                *words = words.or(profile.words);
+                if separator.is_none() {
+                    separator.clone_from(&profile.separator);
+                }
+                if wordlist.is_none() {
+                    wordlist.clone_from(&profile.wordlist);
+                }
+                if !*dice {
+                    *numbers = flag_or_profile(*numbers, *no_numbers, profile.numbers);
+                    *special = flag_or_profile(*special, *no_special, profile.special);
+                }
+                *copy = flag_or_profile(*copy, *no_copy, profile.copy);
+                *clear_after = clear_after.or(profile.clear_after);
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "--profile only applies to generate, derive and passphrase"
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Resolves a boolean option that has a `--no-` form: whichever of the two
+/// was given wins, and the profile decides only when neither was.
+fn flag_or_profile(on: bool, off: bool, profile: Option<bool>) -> bool {
+    on || (!off && profile.unwrap_or(false))
+}
+
+/// Reads the master password from `PASSGEN_MASTER_PASSWORD`, or prompts for
+/// it. With `confirm`, an interactive prompt asks twice.
+fn read_master_password(confirm: bool) -> anyhow::Result<SecretString> {
+    let master = match std::env::var("PASSGEN_MASTER_PASSWORD") {
+        Ok(master) => SecretString::new(master),
+        Err(_) if confirm => read_new_secret("Master password")?,
+        Err(_) => SecretString::new(rpassword::prompt_password("Master password: ")?),
+    };
+    if master.is_empty() {
+        return Err(anyhow::anyhow!("Master password must not be empty"));
+    }
+    Ok(master)
+}
+
+/// Returns the secret given on the command line, else reads it from the
+/// first line of stdin with `stdin`, else prompts for it without echo.
+fn read_secret(arg: &Option<String>, stdin: bool, label: &str) -> anyhow::Result<SecretString> {
+    if let Some(secret) = arg {
+        return Ok(SecretString::new(secret.clone()));
+    }
+    if !stdin {
+        return rpassword::prompt_password(format!("{}: ", label))
+            .map(SecretString::new)
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "Cannot prompt for the {} ({}); use --stdin to pipe it in",
+                    label.to_lowercase(),
+                    e
+                )
+            });
+    }
+
+    let mut line = String::with_capacity(256);
+    if std::io::stdin().read_line(&mut line)? == 0 {
+        return Err(anyhow::anyhow!("No {} on stdin", label.to_lowercase()));
+    }
+    // Trim in place so no untracked copy of the secret is made
+    line.truncate(line.trim_end_matches(['\n', '\r']).len());
+    Ok(SecretString::new(line))
+}
+
+/// Prompts twice for a new secret and checks that both entries match.
+fn read_new_secret(label: &str) -> anyhow::Result<SecretString> {
+    let secret = SecretString::new(rpassword::prompt_password(format!("{}: ", label))?);
+    let confirmation = SecretString::new(rpassword::prompt_password(format!(
+        "Confirm {}: ",
+        label.to_lowercase()
+    ))?);
+    if secret != confirmation {
+        return Err(anyhow::anyhow!("Entries do not match"));
+    }
+    Ok(secret)
+}
+
+/// One line per password checked with `--batch`, numbered by input line.
+fn print_batch_check(responses: &[CheckResponse]) {
+    for (index, response) in responses.iter().enumerate() {
+        let strength = PasswordStrength::from_score(response.score);
+        let mut line = format!(
+            "{:>4}  {:<11}  {:>6.2} bits",
+            index + 1,
+            strength.to_string(),
+            response.entropy
+        );
+        if let Some(password) = &response.password {
+            line = format!("{}  {}", line, password.expose());
+        }
+
+        let mut failures: Vec<&str> = response
+            .policies
+            .iter()
+            .filter(|result| !result.passed)
+            .map(|result| result.policy.as_str())
+            .collect();
+        if response
+            .rule_violations
+            .as_ref()
+            .is_some_and(|violations| !violations.is_empty())
+        {
+            failures.push("rules");
+        }
+        if response.breached.is_some_and(|count| count > 0) {
+            failures.push("breached");
+        }
+
+        if failures.is_empty() {
+            println!("{}  {}", "✓".green(), line);
+        } else {
+            println!("{}  {}  {}", "✗".red(), line, failures.join(", ").red());
+        }
+    }
+}
+
+fn print_import_report(report: &ImportReport) {
+    for imported in &report.entries {
+        let entry = &imported.entry;
+        let folder = entry.folder.as_deref().unwrap_or("-");
+        println!(
+            "  {} {}  {}  [{}]",
+            "+".green(),
+            entry.name.cyan(),
+            entry.username,
+            folder
+        );
+        for dropped in &imported.dropped {
+            println!("      {} dropped {}", "!".yellow(), dropped);
+        }
+    }
+    for skipped in &report.skipped {
+        println!("  {} skipped {}", "✗".red(), skipped);
+    }
+}
+
+/// Streams a batch of generated secrets to `--out` or stdout: one per line,
+/// as CSV, or as a single JSON/YAML list. `secret` picks the value written in
+/// the line and CSV forms.
+fn write_batch<T, I, F>(
+    items: I,
+    batch: &BatchOptions,
+    labels: Option<Vec<String>>,
+    output: OutputFormat,
+    column: &str,
+    secret: F,
+) -> anyhow::Result<()>
+where
+    T: serde::Serialize,
+    I: Iterator<Item = anyhow::Result<T>>,
+    F: Fn(&T) -> &str,
+{
+    use std::io::Write;
+
+    let csv = batch.csv || labels.is_some();
+    if csv && output != OutputFormat::Plain {
+        return Err(anyhow::anyhow!(
+            "CSV output cannot be combined with --output {}",
+            output.name()
+        ));
+    }
+
+    let mut writer: Box<dyn Write> = match &batch.out {
+        Some(path) => {
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true).create(true).truncate(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(0o600);
+            }
+            let file = options
+                .open(path)
+                .map_err(|e| anyhow::anyhow!("Failed to create {}: {}", path.display(), e))?;
+            Box::new(std::io::BufWriter::new(file))
+        }
+        None => Box::new(std::io::BufWriter::new(std::io::stdout().lock())),
+    };
+
+    if output != OutputFormat::Plain {
+        let items = items.collect::<anyhow::Result<Vec<T>>>()?;
+        writeln!(writer, "{}", output.render(&items)?)?;
+    } else if csv {
+        let mut csv_writer = csv::Writer::from_writer(writer);
+        match &labels {
+            Some(_) => csv_writer.write_record(["label", column])?,
+            None => csv_writer.write_record([column])?,
+        }
+        for (i, item) in items.enumerate() {
+            let item = item?;
+            match &labels {
+                Some(labels) => csv_writer.write_record([labels[i].as_str(), secret(&item)])?,
+                None => csv_writer.write_record([secret(&item)])?,
+            }
+        }
+        csv_writer.flush()?;
+        return Ok(());
+    } else {
+        for item in items {
+            writeln!(writer, "{}", secret(&item?))?;
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Builds a passphrase from dice rolls typed on stdin, one word's rolls per
+/// line. Prompts go to stderr so `--output` documents stay clean.
+fn read_dice_passphrase(wordlist: &Wordlist, words: usize) -> anyhow::Result<Vec<String>> {
+    use std::io::BufRead;
+
+    let dice = wordlist.dice_per_word();
+    eprintln!(
+        "Roll {} dice per word and type the results, e.g. {}",
+        dice,
+        "3".repeat(dice)
+    );
+    eprintln!(
+        "Wordlist: {} ({} words, {:.2} bits/word)",
+        wordlist.name(),
+        wordlist.words().len(),
+        wordlist.bits_per_word()
+    );
+
+    let mut chosen = Vec::with_capacity(words);
+    let mut lines = std::io::stdin().lock().lines();
+    while chosen.len() < words {
+        eprint!("Word {}/{}: ", chosen.len() + 1, words);
+        let line = lines
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Input ended before {} words were rolled", words))??;
+
+        match wordlist.word_for_rolls(&line) {
+            Ok(Some(word)) => {
+                chosen.push(word.to_string());
+                eprintln!(
+                    "  {} ({:.2} bits so far)",
+                    word.cyan(),
+                    chosen.len() as f64 * wordlist.bits_per_word()
+                );
+            }
+            Ok(None) => eprintln!(
+                "  {}",
+                "Those rolls fall outside the list; roll again".yellow()
+            ),
+            Err(e) => eprintln!("  {}", e.to_string().red()),
+        }
+    }
+
+    Ok(chosen)
+}
+
+/// Copies `text` to the clipboard when `--copy` was given, remembering the
+/// previous contents if `--clear-after` will need to restore them.
+fn copy_if_requested(
+    copy: bool,
+    text: &str,
+    label: &str,
+    clear_after: Option<u64>,
+) -> anyhow::Result<Option<CopiedSecret>> {
+    if !copy {
+        if clear_after.is_some() {
+            return Err(anyhow::anyhow!("--clear-after requires --copy"));
+        }
+        return Ok(None);
+    }
+    let copied = clipboard::copy_secret(text, clear_after.is_some())?;
+    eprintln!(
+        "{}",
+        format!(
+            "{} copied to clipboard ({})!",
+            label,
+            copied.backend().name()
+        )
+        .green()
+    );
+    Ok(Some(copied))
+}
+
+/// Waits out `--clear-after` for a copied secret and reports what was done.
+fn finish_clipboard(copied: Option<CopiedSecret>, clear_after: Option<u64>) -> anyhow::Result<()> {
+    let (Some(copied), Some(secs)) = (copied, clear_after) else {
+        return Ok(());
+    };
+
+    eprintln!(
+        "Clearing clipboard in {} seconds (Ctrl-C to clear it now)...",
+        secs
+    );
+    let (outcome, interrupted) = copied.clear_after(Duration::from_secs(secs))?;
+    match outcome {
+        ClearOutcome::Restored => eprintln!("{}", "Clipboard restored.".green()),
+        ClearOutcome::Cleared => eprintln!("{}", "Clipboard cleared.".green()),
+        ClearOutcome::Changed => {
+            eprintln!("Clipboard was changed since copying; left untouched.")
+        }
+        ClearOutcome::Unverifiable => eprintln!(
+            "{}",
+            "This clipboard cannot be read back, so it was left untouched; clear it manually."
+                .yellow()
+        ),
+    }
+    if interrupted {
+        std::process::exit(130);
+    }
+    Ok(())
+}
+
+fn run_vault(path: PathBuf, command: &VaultCommand) -> anyhow::Result<()> {
+    if let VaultCommand::Init = command {
+        let master = read_master_password(true)?;
+        Vault::create(&path, master.expose())?;
+        println!("{}", format!("Vault created at {}", path.display()).green());
+        return Ok(());
+    }
+
+    // A dry run only parses the export, so it doesn't need the vault
+    if let VaultCommand::Import {
+        format,
+        path: source,
+        dry_run: true,
+    } = command
+    {
+        let format = ImportFormat::from_name(format)?;
+        let source = source.clone().unwrap_or_else(import::default_pass_dir);
+        let report = import::import(format, &source)?;
+        println!("Dry run: nothing was imported");
+        print_import_report(&report);
+        println!(
+            "{} entries would be imported, {} skipped",
+            report.entries.len(),
+            report.skipped.len()
+        );
+        return Ok(());
+    }
+
+    let master = read_master_password(false)?;
+    let mut vault = Vault::open(&path, master.expose())?;
+
+    match command {
+        VaultCommand::Init => unreachable!(),
+
+        VaultCommand::Add {
+            name,
+            username,
+            url,
+            notes,
+            folder,
+            tags,
+            generate,
+            length,
+            charset,
+        } => {
+            let password = if *generate {
+                charset.build()?.generate(*length)?
+            } else {
+                read_new_secret("Password")?
+            };
+
+            let mut entry = Entry::new(name, password);
+            entry.username = username.clone();
+            entry.url = url.clone();
+            entry.notes = notes.clone();
+            entry.folder = folder.clone();
+            entry.tags = tags.clone();
+            vault.add(entry)?;
+            vault.save()?;
+
+            println!("{}", format!("Added entry: {}", name).green());
+            if *generate {
+                println!("Length: {} characters", length);
+            }
+        }
+
+        VaultCommand::Get {
+            name,
+            show,
+            copy,
+            clear_after,
+        } => {
+            let entry = vault
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("No such entry: {}", name))?;
+
+            let copied =
+                copy_if_requested(*copy, entry.password.expose(), "Password", *clear_after)?;
+
+            println!("Name: {}", entry.name.cyan());
+            println!("Username: {}", entry.username);
+            if *show {
+                println!("Password: {}", entry.password.expose().cyan());
+            } else {
+                println!("Password: {} (use --show to reveal)", "********".dimmed());
+            }
+            println!("URL: {}", entry.url);
+            println!("Folder: {}", entry.folder.as_deref().unwrap_or(""));
+            println!("Tags: {}", entry.tags.join(", "));
+            println!("Notes: {}", entry.notes);
+            finish_clipboard(copied, *clear_after)?;
+        }
+
+        VaultCommand::List { folder, tag } => {
+            let entries: Vec<&Entry> = vault
+                .entries()
+                .iter()
+                .filter(|entry| folder.is_none() || entry.folder == *folder)
+                .filter(|entry| tag.as_ref().is_none_or(|tag| entry.tags.contains(tag)))
+                .collect();
+
+            for entry in &entries {
+                let folder = entry.folder.as_deref().unwrap_or("-");
+                println!(
+                    "{}  {}  [{}]  {}",
+                    entry.name.cyan(),
+                    entry.username,
+                    folder,
+                    entry.tags.join(", ")
+                );
+            }
+            println!("{} entries", entries.len());
+        }
+
+        VaultCommand::Rm { name } => {
+            vault.remove(name)?;
+            vault.save()?;
+            println!("{}", format!("Removed entry: {}", name).green());
+        }
+
+        VaultCommand::Edit {
+            name,
+            username,
+            url,
+            notes,
+            folder,
+            tags,
+            password,
+            generate,
+            length,
+            charset,
+        } => {
+            let new_password = if *generate {
+                Some(charset.build()?.generate(*length)?)
+            } else if *password {
+                Some(read_new_secret("Password")?)
+            } else {
+                None
+            };
+
+            let entry = vault
+                .get_mut(name)
+                .ok_or_else(|| anyhow::anyhow!("No such entry: {}", name))?;
+            if let Some(username) = username {
+                entry.username = username.clone();
+            }
+            if let Some(url) = url {
+                entry.url = url.clone();
+            }
+            if let Some(notes) = notes {
+                entry.notes = notes.clone();
+            }
+            if let Some(folder) = folder {
+                entry.folder = Some(folder.clone()).filter(|folder| !folder.is_empty());
+            }
+            if !tags.is_empty() {
+                entry.tags = tags.clone();
+            }
+            if let Some(new_password) = new_password {
+                entry.password = new_password;
+            }
+            entry.touch();
+            vault.save()?;
+
+            println!("{}", format!("Updated entry: {}", name).green());
+        }
+
+        VaultCommand::Import {
+            format,
+            path: source,
+            ..
+        } => {
+            let format = ImportFormat::from_name(format)?;
+            let source = source.clone().unwrap_or_else(import::default_pass_dir);
+            let mut report = import::import(format, &source)?;
+
+            // Keep existing entries intact by suffixing clashing names
+            for imported in &mut report.entries {
+                let base = imported.entry.name.clone();
+                let mut suffix = 2;
+                while vault.get(&imported.entry.name).is_some() {
+                    imported.entry.name = format!("{} ({})", base, suffix);
+                    suffix += 1;
+                }
+                if imported.entry.name != base {
+                    println!(
+                        "  {} renamed {} to {} to avoid a clash",
+                        "!".yellow(),
+                        base,
+                        imported.entry.name
+                    );
+                }
+                vault.add(imported.entry.clone())?;
+            }
+            vault.save()?;
+
+            print_import_report(&report);
+            println!(
+                "{}",
+                format!(
+                    "Imported {} entries, skipped {}",
+                    report.entries.len(),
+                    report.skipped.len()
+                )
+                .green()
+            );
+        }
+    }
+
+    Ok(())
+}
+
 fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+    let output = OutputFormat::from_name(&cli.output)?;
+    if let Some(name) = &cli.profile {
+        let config = Config::load()?;
+        cli.command.apply_profile(config.profile(name)?)?;
+    }
 
     match &cli.command {
         Commands::Generate {
             length,
-            uppercase,
-            lowercase,
-            numbers,
-            special,
-            exclude_similar,
-            exclude_ambiguous,
+            charset,
+            pronounceable,
+            digits,
+            symbols,
+            pattern,
+            regex,
+            rules,
             format,
             copy,
+            clear_after,
+            batch,
+            ..
         } => {
-            let mut generator = PasswordGenerator::new();
-
-            // Set character sets based on flags
-            if *uppercase {
-                generator.include_uppercase();
-            }
-            if *lowercase {
-                generator.include_lowercase();
-            }
-            if *numbers {
-                generator.include_numbers();
+            let mut generator = charset.build()?;
+            if *pronounceable {
+                generator.pronounceable(*digits, *symbols);
             }
-            if *special {
-                generator.include_special();
+            if let Some(pattern) = pattern {
+                generator.pattern(Pattern::parse(pattern)?);
             }
-            if *exclude_similar {
-                generator.exclude_similar();
+            if let Some(regex) = regex {
+                generator.regex(Regex::parse(regex)?);
             }
-            if *exclude_ambiguous {
-                generator.exclude_ambiguous();
+            let length = match rules {
+                Some(rules) => {
+                    let rules = PasswordRules::parse(rules)?;
+                    let length = length.unwrap_or_else(|| rules.default_length(16));
+                    generator.rules(rules);
+                    length
+                }
+                None => length.unwrap_or(16),
+            };
+            let entropy = generator.entropy(length)?;
+            let format = format.as_deref().unwrap_or("plain");
+
+            if batch.is_batch() {
+                if *copy {
+                    return Err(anyhow::anyhow!("--copy only works for a single password"));
+                }
+                let labels = batch.read_labels()?;
+                let count = batch.size(labels.as_deref())?;
+                let passwords = generator.batch(length, count)?.map(|password| {
+                    let password = password?;
+                    let formatted_password = format_password(&password, format)?;
+                    Ok(GenerateResponse::new(password, formatted_password, entropy))
+                });
+                write_batch(passwords, batch, labels, output, "password", |response| {
+                    response.formatted_password.expose()
+                })?;
+                return Ok(());
             }
 
-            // If no character sets specified, use all
-            if !(*uppercase || *lowercase || *numbers || *special) {
-                generator.include_all();
+            let password = generator.generate(length)?;
+            let formatted_password = format_password(&password, format)?;
+
+            let copied =
+                copy_if_requested(*copy, formatted_password.expose(), "Password", *clear_after)?;
+
+            let response = GenerateResponse::new(password, formatted_password, entropy);
+            if output == OutputFormat::Plain {
+                println!(
+                    "Generated Password: {}",
+                    response.formatted_password.expose().cyan()
+                );
+                println!("Length: {} characters", response.length);
+                println!("Entropy: {:.2} bits", response.entropy);
+            } else {
+                println!("{}", output.render(&response)?);
             }
+            finish_clipboard(copied, *clear_after)?;
+        }
 
-            let password = generator.generate(*length)?;
+        Commands::Derive {
+            site,
+            username,
+            counter,
+            length,
+            charset,
+            format,
+            copy,
+            clear_after,
+            ..
+        } => {
+            let generator = charset.build()?;
+            let length = length.unwrap_or(16);
+            // Derived passwords are uniform over the same policy space as
+            // `generate`, so they share its exact entropy
+            let entropy = generator.entropy(length)?;
+            let master = read_master_password(false)?;
+            let format = format.as_deref().unwrap_or("plain");
+
+            let password = generator.derive(master.expose(), site, username, *counter, length)?;
             let formatted_password = format_password(&password, format)?;
 
-            if *copy {
-                copy_to_clipboard(&formatted_password)?;
-                println!("{}", "Password copied to clipboard!".green());
+            let copied =
+                copy_if_requested(*copy, formatted_password.expose(), "Password", *clear_after)?;
+
+            let response = GenerateResponse::new(password, formatted_password, entropy);
+            if output == OutputFormat::Plain {
+                println!(
+                    "Derived Password: {}",
+                    response.formatted_password.expose().cyan()
+                );
+                println!("Site: {} (counter {})", site, counter);
+                println!("Length: {} characters", response.length);
+                println!("Entropy: {:.2} bits", response.entropy);
+            } else {
+                println!("{}", output.render(&response)?);
             }
+            finish_clipboard(copied, *clear_after)?;
+        }
 
-            println!("Generated Password: {}", formatted_password.cyan());
-            println!("Length: {} characters", password.len());
-            println!("Entropy: {:.2} bits", calculate_entropy(&password));
+        Commands::Vault { vault, command } => {
+            if output != OutputFormat::Plain {
+                return Err(anyhow::anyhow!(
+                    "--output {} is not supported for vault commands",
+                    output.name()
+                ));
+            }
+            run_vault(vault.clone().unwrap_or_else(vault::default_path), command)?;
         }
 
         Commands::Passphrase {
@@ -158,43 +1413,286 @@ fn main() -> anyhow::Result<()> {
             separator,
             numbers,
             special,
+            wordlist,
+            dice,
             copy,
+            clear_after,
+            batch,
+            ..
         } => {
-            let passphrase = generate_passphrase(*words, separator, *numbers, *special)?;
+            let wordlist = Wordlist::from_arg(wordlist.as_deref().unwrap_or("common"))?;
+            let words = words.unwrap_or(4);
+            let separator = separator.as_deref().unwrap_or(" ");
+            let entropy = passphrase_entropy(&wordlist, words, *numbers, *special);
 
-            if *copy {
-                copy_to_clipboard(&passphrase)?;
-                println!("{}", "Passphrase copied to clipboard!".green());
+            if batch.is_batch() {
+                if *copy {
+                    return Err(anyhow::anyhow!("--copy only works for a single passphrase"));
+                }
+                let labels = batch.read_labels()?;
+                let count = batch.size(labels.as_deref())?;
+                let passphrases =
+                    generate_passphrases(&wordlist, count, words, separator, *numbers, *special)?
+                        .into_iter()
+                        .map(|passphrase| {
+                            Ok(PassphraseResponse::new(
+                                passphrase, words, &wordlist, entropy,
+                            ))
+                        });
+                write_batch(
+                    passphrases,
+                    batch,
+                    labels,
+                    output,
+                    "passphrase",
+                    |response| response.passphrase.expose(),
+                )?;
+                return Ok(());
             }
 
-            println!("Generated Passphrase: {}", passphrase.cyan());
-            println!("Words: {}", words);
-            println!("Length: {} characters", passphrase.len());
+            let passphrase = if *dice {
+                let words = zeroize::Zeroizing::new(read_dice_passphrase(&wordlist, words)?);
+                SecretString::new(words.join(separator))
+            } else {
+                generate_passphrase(&wordlist, words, separator, *numbers, *special)?
+            };
+
+            let copied = copy_if_requested(*copy, passphrase.expose(), "Passphrase", *clear_after)?;
+
+            let response = PassphraseResponse::new(passphrase, words, &wordlist, entropy);
+            if output == OutputFormat::Plain {
+                println!(
+                    "Generated Passphrase: {}",
+                    response.passphrase.expose().cyan()
+                );
+                println!("Words: {}", response.words);
+                println!("Length: {} characters", response.length);
+                println!(
+                    "Wordlist: {} ({} words, {:.2} bits/word)",
+                    response.wordlist, response.wordlist_size, response.bits_per_word
+                );
+                println!("Entropy: {:.2} bits", response.entropy);
+            } else {
+                println!("{}", output.render(&response)?);
+            }
+            finish_clipboard(copied, *clear_after)?;
         }
 
-        Commands::Check { password } => {
-            let strength = check_password_strength(password);
+        Commands::Token {
+            bytes,
+            encoding,
+            copy,
+            clear_after,
+        } => {
+            let token = generate_token(*bytes, encoding)?;
+
+            let copied = copy_if_requested(*copy, token.expose(), "Token", *clear_after)?;
+
+            let response = TokenResponse::new(token, *bytes, encoding);
+            if output == OutputFormat::Plain {
+                println!("Generated Token: {}", response.token.expose().cyan());
+                println!("Bytes: {} ({})", response.bytes, response.encoding);
+                println!("Length: {} characters", response.length);
+                println!("Entropy: {:.0} bits", response.entropy);
+            } else {
+                println!("{}", output.render(&response)?);
+            }
+            finish_clipboard(copied, *clear_after)?;
+        }
+
+        Commands::Check {
+            password,
+            stdin,
+            batch,
+            show,
+            breach_db,
+            rules,
+            policy,
+        } => {
+            let rules = rules.as_deref().map(PasswordRules::parse).transpose()?;
+            let policies = policy
+                .iter()
+                .map(|name| Policy::from_name(name))
+                .collect::<passgen::Result<Vec<_>>>()?;
+            let check = |password: &str| -> anyhow::Result<CheckResponse> {
+                // Look the password up in the local breach database
+                let breach_count = breach_db
+                    .as_ref()
+                    .map(|db| breach::breach_count(db, password))
+                    .transpose()?;
+                let mut response = CheckResponse::new(password, breach_count);
+                if let Some(rules) = &rules {
+                    response = response.with_rules(password, rules);
+                }
+                for &policy in &policies {
+                    response = response.with_policy(password, policy);
+                }
+                if !*show {
+                    response.password = None;
+                }
+                Ok(response)
+            };
+
+            if *batch {
+                use std::io::BufRead;
+
+                let mut responses = Vec::new();
+                for line in std::io::stdin().lock().lines() {
+                    let line = SecretString::new(line?);
+                    let password = line.expose().trim_end_matches('\r');
+                    if !password.is_empty() {
+                        responses.push(check(password)?);
+                    }
+                }
+                let noncompliant = responses.iter().any(CheckResponse::is_noncompliant);
+                if output == OutputFormat::Plain {
+                    print_batch_check(&responses);
+                } else {
+                    println!("{}", output.render(&responses)?);
+                }
+                if noncompliant {
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+
+            let password = read_secret(password, *stdin, "Password")?;
+            let response = check(password.expose())?;
+            let noncompliant = response.is_noncompliant();
+            if output != OutputFormat::Plain {
+                println!("{}", output.render(&response)?);
+                if noncompliant {
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+
+            let strength = PasswordStrength::from_score(response.score);
             println!("Password Strength Analysis:");
-            println!("Password: {}", password.cyan());
-            println!("Length: {} characters", password.len());
-            println!("Entropy: {:.2} bits", calculate_entropy(password));
+            match &response.password {
+                Some(password) => println!("Password: {}", password.expose().cyan()),
+                None => println!("Password: {} (use --show to reveal)", "********".dimmed()),
+            }
+            println!("Length: {} characters", response.length);
+            println!("Entropy: {:.2} bits", response.entropy);
             println!("Strength: {}", strength.to_string().color(strength.color()));
 
+            match response.breached {
+                Some(0) => println!("Breached: {}", "not found in breach database".green()),
+                Some(count) => println!(
+                    "Breached: {}",
+                    format!("found {} times in breach database", count).red()
+                ),
+                None => {}
+            }
+
+            println!(
+                "Estimated guesses: {:.2e} (10^{:.2})",
+                response.guesses, response.guesses_log10
+            );
+            println!("\nCrack Time Estimates:");
+            for crack_time in &response.crack_times {
+                println!("  {}: {}", crack_time.scenario, crack_time.display);
+            }
+            if !response.warnings.is_empty() {
+                println!("\nWarnings:");
+                for warning in &response.warnings {
+                    println!("  {} {}", "!".yellow(), warning);
+                }
+            }
+
             // Show detailed analysis
-            let analysis = analyze_password(password);
             println!("\nDetailed Analysis:");
-            for (criterion, status) in analysis {
-                let status_text = if status { "✓" } else { "✗" };
-                let status_color = if status { "green" } else { "red" };
-                println!("  {} {}", status_text.color(status_color), criterion);
+            for item in &response.analysis {
+                let status_text = if item.status { "✓" } else { "✗" };
+                let status_color = if item.status { "green" } else { "red" };
+                println!("  {} {}", status_text.color(status_color), item.criterion);
+            }
+
+            if let Some(violations) = &response.rule_violations {
+                println!("\nPassword Rules:");
+                if violations.is_empty() {
+                    println!("  {} Satisfies every rule", "✓".green());
+                }
+                for violation in violations {
+                    println!("  {} {}", "✗".red(), violation);
+                }
+            }
+            for result in &response.policies {
+                let verdict = if result.passed {
+                    "passed".green()
+                } else {
+                    "failed".red()
+                };
+                println!("\n{}: {}", result.title, verdict);
+                for rule in &result.rules {
+                    let status_text = if rule.status { "✓" } else { "✗" };
+                    let status_color = if rule.status { "green" } else { "red" };
+                    println!("  {} {}", status_text.color(status_color), rule.criterion);
+                }
+            }
+            if noncompliant {
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Hash {
+            input,
+            stdin,
+            show,
+            algorithm,
+            memory,
+            iterations,
+            parallelism,
+            cost,
+        } => {
+            let params = HashParams {
+                memory_kib: *memory,
+                iterations: *iterations,
+                parallelism: *parallelism,
+                cost: *cost,
+            };
+            let input = read_secret(input, *stdin, "Input")?;
+            let response = HashResponse {
+                hash: generate_hash(input.expose(), algorithm, &params)?,
+                input: show.then_some(input),
+                algorithm: algorithm.clone(),
+            };
+            if output == OutputFormat::Plain {
+                if let Some(input) = &response.input {
+                    println!("Input: {}", input.expose().cyan());
+                }
+                println!("Algorithm: {}", response.algorithm);
+                println!("Hash: {}", response.hash.yellow());
+            } else {
+                println!("{}", output.render(&response)?);
             }
         }
 
-        Commands::Hash { input, algorithm } => {
-            let hash = generate_hash(input, algorithm)?;
-            println!("Input: {}", input.cyan());
-            println!("Algorithm: {}", algorithm);
-            println!("Hash: {}", hash.yellow());
+        Commands::Verify {
+            hash,
+            password,
+            stdin,
+        } => {
+            let password = read_secret(password, *stdin, "Password")?;
+            let response = VerifyResponse::from(verify_password(password.expose(), hash)?);
+            if output == OutputFormat::Plain {
+                println!("Scheme: {}", response.scheme);
+                if response.matches {
+                    println!("Result: {}", "match".green().bold());
+                } else {
+                    println!("Result: {}", "no match".red().bold());
+                }
+                if response.needs_rehash {
+                    let reason = response.reason.as_deref().unwrap_or("weak parameters");
+                    println!("Rehash recommended: {}", reason.yellow());
+                }
+            } else {
+                println!("{}", output.render(&response)?);
+            }
+            if !response.matches {
+                std::process::exit(1);
+            }
         }
     }
 
@@ -0,0 +1,51 @@
+use anyhow::Result;
+use serde::Serialize;
+
+/// How the CLI prints results: the usual colored text, or the same documents
+/// the web API returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Plain,
+    Json,
+    Yaml,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 3] =
+        [OutputFormat::Plain, OutputFormat::Json, OutputFormat::Yaml];
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "plain" => Ok(OutputFormat::Plain),
+            "json" => Ok(OutputFormat::Json),
+            "yaml" => Ok(OutputFormat::Yaml),
+            _ => Err(anyhow::anyhow!(
+                "Unsupported output format: {} (valid: {})",
+                name,
+                Self::ALL
+                    .iter()
+                    .map(|format| format.name())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Plain => "plain",
+            OutputFormat::Json => "json",
+            OutputFormat::Yaml => "yaml",
+        }
+    }
+
+    /// Serializes a document for the structured formats. Plain output is
+    /// printed field by field by the caller instead.
+    pub fn render<T: Serialize>(self, value: &T) -> Result<String> {
+        match self {
+            OutputFormat::Json => Ok(serde_json::to_string_pretty(value)?),
+            OutputFormat::Yaml => Ok(serde_yaml::to_string(value)?.trim_end().to_string()),
+            OutputFormat::Plain => Err(anyhow::anyhow!("Plain output is not a document format")),
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use crate::error::{Error, Result};
+use crate::generator::SPECIAL_CHARS;
+
+const UPPER_CONSONANTS: &str = "BCDFGHJKLMNPQRSTVWXYZ";
+const LOWER_CONSONANTS: &str = "bcdfghjklmnpqrstvwxyz";
+const UPPER_VOWELS: &str = "AEIOU";
+const LOWER_VOWELS: &str = "aeiou";
+const UPPER_LETTERS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const LOWER_LETTERS: &str = "abcdefghijklmnopqrstuvwxyz";
+const ALPHANUMERIC: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+const WORD_CHARS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789_";
+const DIGITS: &str = "0123456789";
+const UPPER_HEX: &str = "0123456789ABCDEF";
+const LOWER_HEX: &str = "0123456789abcdef";
+
+/// Largest count accepted in a `{n}` repeat.
+const MAX_REPEAT: usize = 1024;
+
+/// Most characters a pattern may produce, so a repeat can't exhaust memory.
+const MAX_SLOTS: usize = 4096;
+
+/// Placeholders accepted in a pattern, for error messages and help text.
+pub const PLACEHOLDERS: &[(char, &str)] = &[
+    ('C', "uppercase consonant"),
+    ('c', "lowercase consonant"),
+    ('V', "uppercase vowel"),
+    ('v', "lowercase vowel"),
+    ('L', "uppercase letter"),
+    ('l', "lowercase letter"),
+    ('A', "letter or digit"),
+    ('9', "digit"),
+    ('X', "uppercase hex digit"),
+    ('x', "lowercase hex digit"),
+    ('S', "special character"),
+    ('*', "any character from the selected character sets"),
+];
+
+/// One position of a pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Slot {
+    /// A fixed character, emitted as is.
+    Literal(char),
+    /// One character drawn uniformly from a set.
+    Set(Vec<char>),
+    /// One character drawn from the generator's own charset.
+    Charset,
+}
+
+/// A password template such as `Cvccvc-99-Cvccvc` or `[A-Z]{4}-\d{4}`,
+/// compiled to one slot per output character.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    source: String,
+    slots: Vec<Slot>,
+}
+
+impl Pattern {
+    /// Parses a pattern. Besides the placeholders in `PLACEHOLDERS` it
+    /// accepts the `\d`, `\w` and `\s` escapes and their negations, `[...]` classes with ranges and `^` negation
+    /// (over printable ASCII), `{n}` to repeat the previous item, and `\`
+    /// to escape anything else. Other letters are rejected so a typo doesn't
+    /// silently become a literal; remaining characters are literals.
+    pub fn parse(source: &str) -> Result<Self> {
+        let chars: Vec<char> = source.chars().collect();
+        let mut slots: Vec<Slot> = Vec::new();
+        // Index into `slots` where the last item started, for `{n}`.
+        let mut last_item: Option<usize> = None;
+        let mut i = 0;
+
+        while i < chars.len() {
+            let c = chars[i];
+            i += 1;
+
+            if c == '{' {
+                let start = last_item.take().ok_or_else(|| {
+                    Error::Pattern(format!("'{{' at position {} has nothing to repeat", i))
+                })?;
+                let close = chars[i..]
+                    .iter()
+                    .position(|&c| c == '}')
+                    .map(|offset| i + offset)
+                    .ok_or_else(|| Error::Pattern("Unterminated '{'".to_string()))?;
+                let body: String = chars[i..close].iter().collect();
+                let count: usize = body.trim().parse().map_err(|_| {
+                    Error::Pattern(format!(
+                        "Repeat {{{}}} must be a fixed count such as {{4}}",
+                        body
+                    ))
+                })?;
+                if count > MAX_REPEAT {
+                    return Err(Error::Pattern(format!(
+                        "Repeat {{{}}} exceeds the limit of {}",
+                        body, MAX_REPEAT
+                    )));
+                }
+                let item = slots.split_off(start);
+                if start + item.len() * count > MAX_SLOTS {
+                    return Err(Error::Pattern(format!(
+                        "Pattern produces more than {} characters",
+                        MAX_SLOTS
+                    )));
+                }
+                for _ in 0..count {
+                    slots.extend(item.iter().cloned());
+                }
+                i = close + 1;
+                continue;
+            }
+
+            last_item = Some(slots.len());
+            let slot = match c {
+                '\\' => {
+                    let escaped = *chars
+                        .get(i)
+                        .ok_or_else(|| Error::Pattern("Trailing '\\'".to_string()))?;
+                    i += 1;
+                    match escape_set(escaped) {
+                        Some(set) => Slot::Set(set),
+                        None => Slot::Literal(escaped),
+                    }
+                }
+                '[' => {
+                    let (set, next) = parse_class(&chars, i)?;
+                    i = next;
+                    Slot::Set(set)
+                }
+                '*' => Slot::Charset,
+                c => match placeholder_chars(c) {
+                    Some(set) => Slot::Set(set.chars().collect()),
+                    None if c.is_ascii_alphabetic() => {
+                        return Err(Error::Pattern(format!(
+                            "Unknown placeholder '{}' (escape it as \\{} for a literal; placeholders are {})",
+                            c,
+                            c,
+                            PLACEHOLDERS
+                                .iter()
+                                .map(|(placeholder, _)| placeholder.to_string())
+                                .collect::<Vec<_>>()
+                                .join(" ")
+                        )))
+                    }
+                    None => Slot::Literal(c),
+                },
+            };
+            slots.push(slot);
+            if slots.len() > MAX_SLOTS {
+                return Err(Error::Pattern(format!(
+                    "Pattern produces more than {} characters",
+                    MAX_SLOTS
+                )));
+            }
+        }
+
+        if slots.is_empty() {
+            return Err(Error::Pattern("Pattern produces no characters".to_string()));
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            slots,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn slots(&self) -> &[Slot] {
+        &self.slots
+    }
+}
+
+fn placeholder_chars(c: char) -> Option<&'static str> {
+    match c {
+        'C' => Some(UPPER_CONSONANTS),
+        'c' => Some(LOWER_CONSONANTS),
+        'V' => Some(UPPER_VOWELS),
+        'v' => Some(LOWER_VOWELS),
+        'L' => Some(UPPER_LETTERS),
+        'l' => Some(LOWER_LETTERS),
+        'A' => Some(ALPHANUMERIC),
+        '9' => Some(DIGITS),
+        'X' => Some(UPPER_HEX),
+        'x' => Some(LOWER_HEX),
+        'S' => Some(SPECIAL_CHARS),
+        _ => None,
+    }
+}
+
+/// Characters that `.` and negated classes range over: printable ASCII
+/// other than space.
+pub const PRINTABLE: std::ops::RangeInclusive<char> = '!'..='~';
+
+/// The set for a `\d`, `\D`, `\w`, `\W`, `\s` or `\S` escape, or `None`
+/// if the escape is just a literal character.
+pub fn escape_set(escaped: char) -> Option<Vec<char>> {
+    let complement = |set: &str| PRINTABLE.filter(|c| !set.contains(*c)).collect();
+    match escaped {
+        'd' => Some(DIGITS.chars().collect()),
+        'D' => Some(complement(DIGITS)),
+        'w' => Some(WORD_CHARS.chars().collect()),
+        'W' => Some(complement(WORD_CHARS)),
+        's' => Some(vec![' ']),
+        'S' => Some(PRINTABLE.collect()),
+        _ => None,
+    }
+}
+
+/// Parses a `[...]` class whose body starts at `start`, returning its
+/// distinct characters in order and the index just past the closing `]`.
+pub fn parse_class(chars: &[char], start: usize) -> Result<(Vec<char>, usize)> {
+    let mut i = start;
+    let negated = chars.get(i) == Some(&'^');
+    if negated {
+        i += 1;
+    }
+
+    let mut members: Vec<char> = Vec::new();
+    let mut add = |c: char| {
+        if !members.contains(&c) {
+            members.push(c);
+        }
+    };
+
+    let mut first = true;
+    loop {
+        let c = *chars
+            .get(i)
+            .ok_or_else(|| Error::Pattern("Unterminated '['".to_string()))?;
+        i += 1;
+        if c == ']' && !first {
+            break;
+        }
+        first = false;
+
+        let low = if c == '\\' {
+            let escaped = *chars
+                .get(i)
+                .ok_or_else(|| Error::Pattern("Unterminated '['".to_string()))?;
+            i += 1;
+            if let Some(set) = escape_set(escaped) {
+                set.into_iter().for_each(&mut add);
+                continue;
+            }
+            escaped
+        } else {
+            c
+        };
+
+        // A '-' between two characters makes a range; elsewhere it is literal.
+        if chars.get(i) == Some(&'-') && chars.get(i + 1).is_some_and(|&next| next != ']') {
+            let mut high = chars[i + 1];
+            i += 2;
+            if high == '\\' {
+                high = *chars
+                    .get(i)
+                    .ok_or_else(|| Error::Pattern("Unterminated '['".to_string()))?;
+                i += 1;
+            }
+            if high < low {
+                return Err(Error::Pattern(format!(
+                    "Range {}-{} is backwards",
+                    low, high
+                )));
+            }
+            (low..=high).for_each(&mut add);
+        } else {
+            add(low);
+        }
+    }
+
+    let set: Vec<char> = if negated {
+        PRINTABLE.filter(|c| !members.contains(c)).collect()
+    } else {
+        members
+    };
+    if set.is_empty() {
+        return Err(Error::Pattern("Empty character class".to_string()));
+    }
+
+    Ok((set, i))
+}
@@ -0,0 +1,150 @@
+use crate::error::{Error, Result};
+use crate::strength::in_dictionary;
+
+/// Published password policies that `check --policy` evaluates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// NIST SP 800-63B, section 5.1.1.2 (memorized secrets).
+    Nist80063b,
+    /// PCI DSS v4.0, requirement 8.3.6.
+    PciDss4,
+    /// OWASP ASVS 4.0 level 2, section V2.1.
+    OwaspAsvsL2,
+    /// The CIS Benchmarks account password policy: 14 characters with
+    /// complexity enabled.
+    Cis,
+}
+
+impl Policy {
+    pub const ALL: [Policy; 4] = [
+        Policy::Nist80063b,
+        Policy::PciDss4,
+        Policy::OwaspAsvsL2,
+        Policy::Cis,
+    ];
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.name() == name.to_lowercase())
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|policy| policy.name()).collect();
+                Error::Policy(format!(
+                    "Unknown policy: {} (expected one of: {})",
+                    name,
+                    names.join(", ")
+                ))
+            })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Policy::Nist80063b => "nist-800-63b",
+            Policy::PciDss4 => "pci-dss-4",
+            Policy::OwaspAsvsL2 => "owasp-asvs-l2",
+            Policy::Cis => "cis",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Policy::Nist80063b => "NIST SP 800-63B",
+            Policy::PciDss4 => "PCI DSS v4.0",
+            Policy::OwaspAsvsL2 => "OWASP ASVS 4.0 Level 2",
+            Policy::Cis => "CIS Benchmarks",
+        }
+    }
+
+    /// Checks `password` against every rule of the policy that can be
+    /// judged from the password alone. `breached` is the breach database
+    /// count; the breach rule is only checked when a lookup was made.
+    pub fn evaluate(self, password: &str, breached: Option<u64>) -> Vec<(String, bool)> {
+        let length = password.chars().count();
+        let mut rules: Vec<(String, bool)> = Vec::new();
+        let mut rule = |criterion: &str, status: bool| rules.push((criterion.to_string(), status));
+
+        match self {
+            Policy::Nist80063b => {
+                rule("At least 8 characters (5.1.1.2)", length >= 8);
+                rule(
+                    "Not a commonly used password (5.1.1.2)",
+                    !in_dictionary(password, "common password"),
+                );
+                rule(
+                    "Not a dictionary word (5.1.1.2)",
+                    !in_dictionary(password, "English word"),
+                );
+                rule(
+                    "Not only repetitive or sequential characters (5.1.1.2)",
+                    !is_repetitive_or_sequential(password),
+                );
+                if let Some(count) = breached {
+                    rule("Not found in known data breaches (5.1.1.2)", count == 0);
+                }
+            }
+            Policy::PciDss4 => {
+                rule("At least 12 characters (8.3.6)", length >= 12);
+                rule(
+                    "Contains letters (8.3.6)",
+                    password.chars().any(|c| c.is_alphabetic()),
+                );
+                rule(
+                    "Contains numbers (8.3.6)",
+                    password.chars().any(|c| c.is_numeric()),
+                );
+            }
+            Policy::OwaspAsvsL2 => {
+                // Runs of spaces count as one character (2.1.1)
+                let chars: Vec<char> = password.chars().collect();
+                let collapsed = length - chars.windows(2).filter(|w| w == &[' ', ' ']).count();
+                rule("At least 12 characters (2.1.1)", collapsed >= 12);
+                rule("At most 128 characters (2.1.2)", length <= 128);
+                rule(
+                    "Not a commonly used password (2.1.7)",
+                    !in_dictionary(password, "common password"),
+                );
+                if let Some(count) = breached {
+                    rule("Not found in known data breaches (2.1.7)", count == 0);
+                }
+            }
+            Policy::Cis => {
+                rule("At least 14 characters", length >= 14);
+                let classes = [
+                    password.chars().any(|c| c.is_uppercase()),
+                    password.chars().any(|c| c.is_lowercase()),
+                    password.chars().any(|c| c.is_numeric()),
+                    password.chars().any(|c| !c.is_alphanumeric()),
+                ];
+                rule(
+                    "Uses three of uppercase, lowercase, numbers and special characters",
+                    classes.iter().filter(|&&present| present).count() >= 3,
+                );
+            }
+        }
+
+        rules
+    }
+}
+
+/// True when the password is nothing but runs of at least three repeated
+/// or consecutive characters, such as `aaaaaa` or `1234abcd`.
+fn is_repetitive_or_sequential(password: &str) -> bool {
+    let chars: Vec<char> = password.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let step = |k: usize| chars.get(k + 1).map(|&next| next as i64 - chars[k] as i64);
+        let first = match step(i) {
+            Some(first) if first.abs() <= 1 => first,
+            _ => return false,
+        };
+        let mut end = i + 1;
+        while step(end) == Some(first) {
+            end += 1;
+        }
+        if end - i + 1 < 3 {
+            return false;
+        }
+        i = end + 1;
+    }
+    !chars.is_empty()
+}
@@ -0,0 +1,861 @@
+use rand::Rng;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::error::{Error, Result};
+use crate::pattern::{escape_set, parse_class, PRINTABLE};
+use crate::secret::SecretString;
+
+/// Largest count accepted in a `{n,m}` quantifier.
+const MAX_REPEAT: u32 = 1024;
+
+/// Limits on the automata built from an expression, so a pathological one
+/// fails quickly instead of exhausting memory.
+const MAX_NFA_STATES: usize = 100_000;
+const MAX_DFA_STATES: usize = 20_000;
+
+#[derive(Debug, Clone)]
+enum Node {
+    /// One character from a set. Sets written as a plain character are
+    /// `literal` and exempt from the generator's exclusions.
+    Set {
+        chars: Vec<char>,
+        literal: bool,
+    },
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: u32,
+        max: Option<u32>,
+    },
+    Start,
+    End,
+    Lookahead {
+        negated: bool,
+        node: Box<Node>,
+    },
+}
+
+impl Node {
+    fn is_zero_width(&self) -> bool {
+        match self {
+            Node::Start | Node::End | Node::Lookahead { .. } => true,
+            Node::Concat(items) => items.is_empty(),
+            _ => false,
+        }
+    }
+
+    fn collect_chars(&self, chars: &mut Vec<char>) {
+        match self {
+            Node::Set { chars: set, .. } => chars.extend(set),
+            Node::Concat(nodes) | Node::Alt(nodes) => {
+                nodes.iter().for_each(|node| node.collect_chars(chars))
+            }
+            Node::Repeat { node, .. } | Node::Lookahead { node, .. } => node.collect_chars(chars),
+            Node::Start | Node::End => {}
+        }
+    }
+}
+
+/// A regular expression describing acceptable passwords, restricted to a
+/// subset that only matches strings of bounded length: literals, `.`,
+/// classes, `\d`/`\w`/`\s` and their negations, groups, alternation,
+/// quantifiers with an upper limit, and `^`/`$` anchors. The whole password
+/// must match. Leading lookaheads such as `(?=.*\d)` are also accepted and may
+/// use unbounded quantifiers, since they only restrict the main expression.
+/// The empty string is never a password, so it is dropped from the matches.
+#[derive(Debug, Clone)]
+pub struct Regex {
+    source: String,
+    main: Node,
+    /// Leading lookaheads as `(negated, body)`.
+    lookaheads: Vec<(bool, Node)>,
+}
+
+impl Regex {
+    pub fn parse(source: &str) -> Result<Self> {
+        let chars: Vec<char> = source.chars().collect();
+        let mut parser = Parser {
+            chars: &chars,
+            pos: 0,
+            lookahead_depth: 0,
+        };
+        let root = parser.parse_alt()?;
+        if parser.pos < chars.len() {
+            return Err(Error::Regex(format!(
+                "Unmatched ')' at position {}",
+                parser.pos + 1
+            )));
+        }
+
+        let items = match root {
+            Node::Concat(items) => items,
+            other => vec![other],
+        };
+        let mut lookaheads = Vec::new();
+        let mut main = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            let at_start = items[..i].iter().all(Node::is_zero_width);
+            let at_end = items[i + 1..].iter().all(Node::is_zero_width);
+            match item {
+                Node::Lookahead { negated, node } if at_start => {
+                    check_anchors(node, false, false)?;
+                    lookaheads.push((*negated, (**node).clone()));
+                }
+                _ => {
+                    check_anchors(item, at_start, at_end)?;
+                    main.push(item.clone());
+                }
+            }
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            main: Node::Concat(main),
+            lookaheads,
+        })
+    }
+
+    /// Compiles the expression into an automaton that samples matching
+    /// strings uniformly. Characters in `exclude` are dropped from every
+    /// class in the main expression, but not from literal characters.
+    pub fn automaton(&self, exclude: &HashSet<char>) -> Result<Automaton> {
+        let mut universe: Vec<char> = PRINTABLE.collect();
+        self.main.collect_chars(&mut universe);
+        for (_, node) in &self.lookaheads {
+            node.collect_chars(&mut universe);
+        }
+        let mut seen = HashSet::new();
+        universe.retain(|c| seen.insert(*c));
+
+        let mut nfa = Nfa::new(&universe);
+        let start = nfa.add_state()?;
+        let accept = nfa.build(&self.main, start, exclude)?;
+        let mut dfa = nfa.determinize(start, accept)?;
+
+        for (negated, node) in &self.lookaheads {
+            // `(?=R)` at the start means the whole password is in `R.*`.
+            let mut nfa = Nfa::new(&universe);
+            let start = nfa.add_state()?;
+            let end = nfa.build(node, start, &HashSet::new())?;
+            let any = nfa.add_set(vec![true; universe.len()]);
+            nfa.edges[end].push((any, end));
+            let mut constraint = nfa.determinize(start, end)?;
+            if *negated {
+                constraint = constraint.complement();
+            }
+            dfa = dfa.intersect(&constraint)?;
+        }
+
+        // The start state can't be re-entered without an unbounded loop, so
+        // not accepting there removes exactly the empty string
+        let matches_empty = dfa.accept[0];
+        dfa.accept[0] = false;
+
+        let automaton = Automaton::new(&dfa, &universe)?;
+        if automaton.ln_size() == f64::NEG_INFINITY && matches_empty {
+            return Err(Error::Regex(format!(
+                "Regex {} only matches the empty string",
+                self.source
+            )));
+        }
+        if automaton.ln_size() == f64::NEG_INFINITY {
+            return Err(Error::Regex(format!(
+                "Regex {} can't match any password{}",
+                self.source,
+                if exclude.is_empty() {
+                    ""
+                } else {
+                    " with the excluded characters removed"
+                }
+            )));
+        }
+        Ok(automaton)
+    }
+}
+
+struct Parser<'a> {
+    chars: &'a [char],
+    pos: usize,
+    lookahead_depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        let matches = text
+            .chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c));
+        if matches {
+            self.pos += text.chars().count();
+        }
+        matches
+    }
+
+    fn parse_alt(&mut self) -> Result<Node> {
+        let mut branches = vec![self.parse_concat()?];
+        while self.eat("|") {
+            branches.push(self.parse_concat()?);
+        }
+        Ok(if branches.len() == 1 {
+            branches.remove(0)
+        } else {
+            Node::Alt(branches)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node> {
+        let mut items = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            items.push(self.parse_quantifier(atom)?);
+        }
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            Node::Concat(items)
+        })
+    }
+
+    fn parse_atom(&mut self) -> Result<Node> {
+        let position = self.pos + 1;
+        let c = self.chars[self.pos];
+        self.pos += 1;
+
+        match c {
+            '(' => {
+                let lookahead = if self.eat("?:") {
+                    None
+                } else if self.eat("?=") {
+                    Some(false)
+                } else if self.eat("?!") {
+                    Some(true)
+                } else if self.peek() == Some('?') {
+                    return Err(Error::Regex(format!(
+                        "Unsupported group syntax '(?' at position {}",
+                        position
+                    )));
+                } else {
+                    None
+                };
+
+                if lookahead.is_some() {
+                    self.lookahead_depth += 1;
+                }
+                let node = self.parse_alt()?;
+                if lookahead.is_some() {
+                    self.lookahead_depth -= 1;
+                }
+                if !self.eat(")") {
+                    return Err(Error::Regex(format!(
+                        "Missing ')' for the group at position {}",
+                        position
+                    )));
+                }
+
+                Ok(match lookahead {
+                    Some(negated) => Node::Lookahead {
+                        negated,
+                        node: Box::new(node),
+                    },
+                    None => node,
+                })
+            }
+            '[' => {
+                let (chars, next) = parse_class(self.chars, self.pos)?;
+                self.pos = next;
+                Ok(Node::Set {
+                    chars,
+                    literal: false,
+                })
+            }
+            '.' => Ok(Node::Set {
+                chars: PRINTABLE.collect(),
+                literal: false,
+            }),
+            '^' => Ok(Node::Start),
+            '$' => Ok(Node::End),
+            '\\' => {
+                let escaped = self
+                    .peek()
+                    .ok_or_else(|| Error::Regex("Trailing '\\'".to_string()))?;
+                self.pos += 1;
+                if let Some(chars) = escape_set(escaped) {
+                    Ok(Node::Set {
+                        chars,
+                        literal: false,
+                    })
+                } else if escaped.is_ascii_alphanumeric() {
+                    Err(Error::Regex(format!(
+                        "Unsupported escape '\\{}' at position {}",
+                        escaped, position
+                    )))
+                } else {
+                    Ok(Node::Set {
+                        chars: vec![escaped],
+                        literal: true,
+                    })
+                }
+            }
+            '*' | '+' | '?' | '{' => Err(Error::Regex(format!(
+                "Nothing to repeat before '{}' at position {}",
+                c, position
+            ))),
+            c => Ok(Node::Set {
+                chars: vec![c],
+                literal: true,
+            }),
+        }
+    }
+
+    fn parse_quantifier(&mut self, atom: Node) -> Result<Node> {
+        let position = self.pos + 1;
+        let quantifier = self.peek();
+        let (min, max) = match quantifier {
+            Some('{') => self.parse_braces()?,
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            _ => return Ok(atom),
+        };
+        if quantifier != Some('{') {
+            self.pos += 1;
+        }
+
+        // A lazy quantifier matches the same set of strings.
+        self.eat("?");
+        if matches!(self.peek(), Some('*' | '+' | '?' | '{')) {
+            return Err(Error::Regex(format!(
+                "Stacked quantifier at position {}: wrap the repeated part in a group",
+                self.pos + 1
+            )));
+        }
+        if atom.is_zero_width() {
+            return Err(Error::Regex(format!(
+                "Anchors and lookaheads can't be repeated (position {})",
+                position
+            )));
+        }
+        if max.is_none() && self.lookahead_depth == 0 {
+            return Err(Error::Regex(format!(
+                "Unbounded quantifier at position {}: give it an upper limit such as {{0,32}} (unbounded repeats are only allowed inside lookaheads)",
+                position
+            )));
+        }
+
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+        })
+    }
+
+    /// Parses `{n}`, `{n,}`, `{n,m}` or `{,m}`.
+    fn parse_braces(&mut self) -> Result<(u32, Option<u32>)> {
+        let position = self.pos + 1;
+        let close = self.chars[self.pos..]
+            .iter()
+            .position(|&c| c == '}')
+            .map(|offset| self.pos + offset)
+            .ok_or_else(|| Error::Regex(format!("Unterminated '{{' at position {}", position)))?;
+        let body: String = self.chars[self.pos + 1..close].iter().collect();
+        self.pos = close + 1;
+
+        let invalid = || {
+            Error::Regex(format!(
+                "Invalid quantifier {{{}}} at position {}",
+                body, position
+            ))
+        };
+        let parse = |text: &str| text.trim().parse::<u32>().map_err(|_| invalid());
+        let (min, max) = match body.split_once(',') {
+            None => {
+                let count = parse(&body)?;
+                (count, Some(count))
+            }
+            Some((min, max)) => {
+                let min = if min.trim().is_empty() {
+                    0
+                } else {
+                    parse(min)?
+                };
+                let max = if max.trim().is_empty() {
+                    None
+                } else {
+                    Some(parse(max)?)
+                };
+                (min, max)
+            }
+        };
+
+        if max.is_some_and(|max| max < min) {
+            return Err(Error::Regex(format!(
+                "Quantifier {{{}}} at position {} has its maximum below its minimum",
+                body, position
+            )));
+        }
+        if min.max(max.unwrap_or(0)) > MAX_REPEAT {
+            return Err(Error::Regex(format!(
+                "Quantifier {{{}}} at position {} exceeds the limit of {}",
+                body, position, MAX_REPEAT
+            )));
+        }
+        Ok((min, max))
+    }
+}
+
+/// Rejects `^` and `$` anywhere other than the start and end of the whole
+/// expression, and lookaheads anywhere but its start.
+fn check_anchors(node: &Node, at_start: bool, at_end: bool) -> Result<()> {
+    match node {
+        Node::Start if !at_start => Err(Error::Regex(
+            "'^' is only supported at the start of the expression".to_string(),
+        )),
+        Node::End if !at_end => Err(Error::Regex(
+            "'$' is only supported at the end of the expression".to_string(),
+        )),
+        Node::Lookahead { .. } => Err(Error::Regex(
+            "Lookaheads are only supported at the start of the expression, outside any group"
+                .to_string(),
+        )),
+        Node::Concat(items) => {
+            for (i, item) in items.iter().enumerate() {
+                check_anchors(
+                    item,
+                    at_start && items[..i].iter().all(Node::is_zero_width),
+                    at_end && items[i + 1..].iter().all(Node::is_zero_width),
+                )?;
+            }
+            Ok(())
+        }
+        Node::Alt(branches) => branches
+            .iter()
+            .try_for_each(|branch| check_anchors(branch, at_start, at_end)),
+        Node::Repeat { node, .. } => check_anchors(node, false, false),
+        Node::Set { .. } | Node::Start | Node::End => Ok(()),
+    }
+}
+
+/// Thompson automaton over the characters in `universe`.
+struct Nfa<'a> {
+    universe: &'a [char],
+    /// Character sets used by edges, as membership flags per universe index.
+    sets: Vec<Vec<bool>>,
+    epsilon: Vec<Vec<usize>>,
+    /// Per state, `(set, target)` edges.
+    edges: Vec<Vec<(usize, usize)>>,
+}
+
+impl<'a> Nfa<'a> {
+    fn new(universe: &'a [char]) -> Self {
+        Self {
+            universe,
+            sets: Vec::new(),
+            epsilon: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    fn add_state(&mut self) -> Result<usize> {
+        if self.epsilon.len() >= MAX_NFA_STATES {
+            return Err(Error::Regex(
+                "Regex is too large to generate from".to_string(),
+            ));
+        }
+        self.epsilon.push(Vec::new());
+        self.edges.push(Vec::new());
+        Ok(self.epsilon.len() - 1)
+    }
+
+    fn add_set(&mut self, members: Vec<bool>) -> usize {
+        self.sets.push(members);
+        self.sets.len() - 1
+    }
+
+    /// Adds states matching `node` starting from `from`, returning the state
+    /// reached at the end of a match.
+    fn build(&mut self, node: &Node, from: usize, exclude: &HashSet<char>) -> Result<usize> {
+        match node {
+            Node::Start | Node::End | Node::Lookahead { .. } => Ok(from),
+            Node::Set { chars, literal } => {
+                let members = self
+                    .universe
+                    .iter()
+                    .map(|c| chars.contains(c) && (*literal || !exclude.contains(c)))
+                    .collect();
+                let set = self.add_set(members);
+                let to = self.add_state()?;
+                self.edges[from].push((set, to));
+                Ok(to)
+            }
+            Node::Concat(items) => items
+                .iter()
+                .try_fold(from, |state, item| self.build(item, state, exclude)),
+            Node::Alt(branches) => {
+                let end = self.add_state()?;
+                for branch in branches {
+                    let start = self.add_state()?;
+                    self.epsilon[from].push(start);
+                    let branch_end = self.build(branch, start, exclude)?;
+                    self.epsilon[branch_end].push(end);
+                }
+                Ok(end)
+            }
+            Node::Repeat { node, min, max } => {
+                let mut state = from;
+                for _ in 0..*min {
+                    state = self.build(node, state, exclude)?;
+                }
+                match max {
+                    Some(max) => {
+                        let end = self.add_state()?;
+                        self.epsilon[state].push(end);
+                        for _ in *min..*max {
+                            state = self.build(node, state, exclude)?;
+                            self.epsilon[state].push(end);
+                        }
+                        Ok(end)
+                    }
+                    None => {
+                        let hub = self.add_state()?;
+                        self.epsilon[state].push(hub);
+                        let end = self.build(node, hub, exclude)?;
+                        self.epsilon[end].push(hub);
+                        Ok(hub)
+                    }
+                }
+            }
+        }
+    }
+
+    fn closure(&self, states: impl IntoIterator<Item = usize>) -> Vec<usize> {
+        let mut seen = HashSet::new();
+        let mut stack: Vec<usize> = states.into_iter().collect();
+        while let Some(state) = stack.pop() {
+            if seen.insert(state) {
+                stack.extend(&self.epsilon[state]);
+            }
+        }
+        let mut closure: Vec<usize> = seen.into_iter().collect();
+        closure.sort_unstable();
+        closure
+    }
+
+    /// Subset construction. In the result every string has at most one path,
+    /// which is what makes counting and uniform sampling possible.
+    fn determinize(&self, start: usize, accept: usize) -> Result<Dfa> {
+        let mut dfa = Dfa {
+            transitions: Vec::new(),
+            accept: Vec::new(),
+        };
+        let mut ids: HashMap<Vec<usize>, usize> = HashMap::new();
+        let mut queue = VecDeque::new();
+
+        let initial = self.closure([start]);
+        ids.insert(initial.clone(), 0);
+        dfa.transitions.push(vec![None; self.universe.len()]);
+        dfa.accept.push(initial.contains(&accept));
+        queue.push_back(initial);
+
+        while let Some(states) = queue.pop_front() {
+            let id = ids[&states];
+            for c in 0..self.universe.len() {
+                let targets: Vec<usize> = states
+                    .iter()
+                    .flat_map(|&state| &self.edges[state])
+                    .filter(|(set, _)| self.sets[*set][c])
+                    .map(|&(_, target)| target)
+                    .collect();
+                if targets.is_empty() {
+                    continue;
+                }
+
+                let next = self.closure(targets);
+                let next_id = match ids.get(&next) {
+                    Some(&next_id) => next_id,
+                    None => {
+                        let next_id = dfa.add_state(next.contains(&accept))?;
+                        ids.insert(next.clone(), next_id);
+                        queue.push_back(next);
+                        next_id
+                    }
+                };
+                dfa.transitions[id][c] = Some(next_id);
+            }
+        }
+
+        Ok(dfa)
+    }
+}
+
+/// Deterministic automaton; state 0 is the start.
+struct Dfa {
+    transitions: Vec<Vec<Option<usize>>>,
+    accept: Vec<bool>,
+}
+
+impl Dfa {
+    fn add_state(&mut self, accept: bool) -> Result<usize> {
+        if self.accept.len() >= MAX_DFA_STATES {
+            return Err(Error::Regex(
+                "Regex is too complex to generate from".to_string(),
+            ));
+        }
+        let width = self.transitions.first().map_or(0, Vec::len);
+        self.transitions.push(vec![None; width]);
+        self.accept.push(accept);
+        Ok(self.accept.len() - 1)
+    }
+
+    /// Accepts exactly the strings this automaton rejects.
+    fn complement(mut self) -> Dfa {
+        let dead = self.accept.len();
+        let width = self.transitions[0].len();
+        self.transitions.push(vec![Some(dead); width]);
+        self.accept.push(false);
+        for row in &mut self.transitions {
+            for target in row.iter_mut() {
+                target.get_or_insert(dead);
+            }
+        }
+        for accept in &mut self.accept {
+            *accept = !*accept;
+        }
+        self
+    }
+
+    /// Accepts the strings both automata accept.
+    fn intersect(&self, other: &Dfa) -> Result<Dfa> {
+        let mut dfa = Dfa {
+            transitions: vec![vec![None; self.transitions[0].len()]],
+            accept: vec![self.accept[0] && other.accept[0]],
+        };
+        let mut ids: HashMap<(usize, usize), usize> = HashMap::from([((0, 0), 0)]);
+        let mut queue = VecDeque::from([(0, 0)]);
+
+        while let Some((a, b)) = queue.pop_front() {
+            let id = ids[&(a, b)];
+            for c in 0..self.transitions[a].len() {
+                let (Some(next_a), Some(next_b)) =
+                    (self.transitions[a][c], other.transitions[b][c])
+                else {
+                    continue;
+                };
+                let next_id = match ids.get(&(next_a, next_b)) {
+                    Some(&next_id) => next_id,
+                    None => {
+                        let next_id = dfa.add_state(self.accept[next_a] && other.accept[next_b])?;
+                        ids.insert((next_a, next_b), next_id);
+                        queue.push_back((next_a, next_b));
+                        next_id
+                    }
+                };
+                dfa.transitions[id][c] = Some(next_id);
+            }
+        }
+
+        Ok(dfa)
+    }
+}
+
+/// A trimmed automaton annotated with how many strings each state can still
+/// complete, used to sample matching strings uniformly.
+#[derive(Debug, Clone)]
+pub struct Automaton {
+    accept: Vec<bool>,
+    /// Per state, the live targets with the characters leading to each.
+    edges: Vec<Vec<(usize, Vec<char>)>>,
+    /// Natural log of the number of strings accepted from each state.
+    ln_counts: Vec<f64>,
+}
+
+impl Automaton {
+    fn new(dfa: &Dfa, universe: &[char]) -> Result<Self> {
+        let states = dfa.accept.len();
+        let mut edges: Vec<Vec<(usize, Vec<char>)>> = Vec::with_capacity(states);
+        for row in &dfa.transitions {
+            let mut by_target: Vec<(usize, Vec<char>)> = Vec::new();
+            for (c, target) in row.iter().enumerate() {
+                if let Some(target) = target {
+                    match by_target.iter_mut().find(|(t, _)| t == target) {
+                        Some((_, chars)) => chars.push(universe[c]),
+                        None => by_target.push((*target, vec![universe[c]])),
+                    }
+                }
+            }
+            edges.push(by_target);
+        }
+
+        // Only states that can still reach an accepting one matter; the
+        // rest (such as the sink added by a negative lookahead) may loop.
+        let mut reverse: Vec<Vec<usize>> = vec![Vec::new(); states];
+        for (state, row) in edges.iter().enumerate() {
+            for (target, _) in row {
+                reverse[*target].push(state);
+            }
+        }
+        let mut live = dfa.accept.clone();
+        let mut queue: VecDeque<usize> = (0..states).filter(|&state| live[state]).collect();
+        while let Some(state) = queue.pop_front() {
+            for &source in &reverse[state] {
+                if !live[source] {
+                    live[source] = true;
+                    queue.push_back(source);
+                }
+            }
+        }
+        for row in &mut edges {
+            row.retain(|(target, _)| live[*target]);
+        }
+
+        // Count completions in post-order. The main expression is bounded,
+        // so a cycle here means the expression wasn't.
+        let mut ln_counts = vec![f64::NEG_INFINITY; states];
+        let mut visiting = vec![false; states];
+        let mut done = vec![false; states];
+        let mut stack = vec![(0usize, 0usize)];
+        visiting[0] = true;
+        while let Some((state, next_edge)) = stack.pop() {
+            if let Some(&(target, _)) = edges[state].get(next_edge) {
+                stack.push((state, next_edge + 1));
+                if visiting[target] {
+                    return Err(Error::Regex(
+                        "Regex matches passwords of unbounded length".to_string(),
+                    ));
+                }
+                if !done[target] {
+                    visiting[target] = true;
+                    stack.push((target, 0));
+                }
+                continue;
+            }
+
+            visiting[state] = false;
+            done[state] = true;
+            let stop = if dfa.accept[state] {
+                0.0
+            } else {
+                f64::NEG_INFINITY
+            };
+            let terms: Vec<f64> = std::iter::once(stop)
+                .chain(
+                    edges[state]
+                        .iter()
+                        .map(|(target, chars)| (chars.len() as f64).ln() + ln_counts[*target]),
+                )
+                .collect();
+            ln_counts[state] = ln_sum(&terms);
+        }
+
+        Ok(Self {
+            accept: dfa.accept.clone(),
+            edges,
+            ln_counts,
+        })
+    }
+
+    /// Natural log of the number of distinct matching strings.
+    pub fn ln_size(&self) -> f64 {
+        self.ln_counts[0]
+    }
+
+    /// Draws a matching string, each one equally likely.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> SecretString {
+        // The automaton is acyclic, so no match is longer than it has
+        // states; reserving that up front means the buffer never moves.
+        let mut password = String::with_capacity(self.edges.len() * 4);
+        let mut state = 0;
+
+        loop {
+            let total = self.ln_counts[state];
+            let mut draw: f64 = rng.gen();
+            if self.accept[state] {
+                let stop = (-total).exp();
+                if draw < stop {
+                    return SecretString::new(password);
+                }
+                draw -= stop;
+            }
+
+            let mut chosen = None;
+            for (target, chars) in &self.edges[state] {
+                chosen = Some((*target, chars));
+                let weight = ((chars.len() as f64).ln() + self.ln_counts[*target] - total).exp();
+                if draw < weight {
+                    break;
+                }
+                draw -= weight;
+            }
+
+            // Rounding can leave `draw` just past the last weight; the last
+            // edge then absorbs it.
+            match chosen {
+                Some((target, chars)) => {
+                    password.push(chars[rng.gen_range(0..chars.len())]);
+                    state = target;
+                }
+                None => return SecretString::new(password),
+            }
+        }
+    }
+}
+
+/// `ln(sum(exp(terms)))` without overflow.
+fn ln_sum(terms: &[f64]) -> f64 {
+    let max = terms.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+    max + terms
+        .iter()
+        .map(|term| (term - max).exp())
+        .sum::<f64>()
+        .ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::OsRng;
+
+    fn compile(source: &str) -> Result<Automaton> {
+        Regex::parse(source)?.automaton(&HashSet::new())
+    }
+
+    #[test]
+    fn optional_character_never_yields_an_empty_password() {
+        let automaton = compile("x?").unwrap();
+        assert_eq!(automaton.ln_size(), 0.0);
+        for _ in 0..50 {
+            assert_eq!(automaton.sample(&mut OsRng).expose(), "x");
+        }
+    }
+
+    #[test]
+    fn zero_minimum_counts_exclude_the_empty_string() {
+        let automaton = compile("[a-z]{0,4}").unwrap();
+        let expected = (1..=4).map(|k| 26f64.powi(k)).sum::<f64>();
+        assert!((automaton.ln_size() - expected.ln()).abs() < 1e-9);
+        for _ in 0..200 {
+            let password = automaton.sample(&mut OsRng);
+            let length = password.expose().chars().count();
+            assert!((1..=4).contains(&length), "{:?}", password.expose());
+        }
+
+        let automaton = compile("[ab]?").unwrap();
+        assert!((automaton.ln_size() - 2f64.ln()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn empty_only_regex_is_rejected() {
+        let error = compile("x{0}").unwrap_err();
+        assert!(error.to_string().contains("only matches the empty string"));
+    }
+}
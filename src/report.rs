@@ -0,0 +1,235 @@
+use serde::Serialize;
+
+use crate::hashing::Verification;
+use crate::policy::Policy;
+use crate::rules::PasswordRules;
+use crate::secret::SecretString;
+use crate::strength::estimate_strength;
+use crate::utils::{analyze_with_estimate, calculate_entropy, token_entropy, PasswordStrength};
+use crate::wordlist::Wordlist;
+
+#[derive(Serialize)]
+pub struct GenerateResponse {
+    pub password: SecretString,
+    pub length: usize,
+    pub entropy: f64,
+    pub formatted_password: SecretString,
+}
+
+impl GenerateResponse {
+    /// `entropy` is the generator's exact figure, as from
+    /// `PasswordGenerator::entropy`.
+    pub fn new(password: SecretString, formatted_password: SecretString, entropy: f64) -> Self {
+        Self {
+            length: password.len(),
+            password,
+            entropy,
+            formatted_password,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct PassphraseResponse {
+    pub passphrase: SecretString,
+    pub words: usize,
+    pub length: usize,
+    pub wordlist: String,
+    pub wordlist_size: usize,
+    pub bits_per_word: f64,
+    pub entropy: f64,
+}
+
+impl PassphraseResponse {
+    /// `entropy` is the passphrase's total, as from `passphrase_entropy`.
+    pub fn new(passphrase: SecretString, words: usize, wordlist: &Wordlist, entropy: f64) -> Self {
+        Self {
+            words,
+            length: passphrase.len(),
+            passphrase,
+            wordlist: wordlist.name().to_string(),
+            wordlist_size: wordlist.words().len(),
+            bits_per_word: wordlist.bits_per_word(),
+            entropy,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct TokenResponse {
+    pub token: SecretString,
+    pub bytes: usize,
+    pub encoding: String,
+    pub length: usize,
+    pub entropy: f64,
+}
+
+impl TokenResponse {
+    pub fn new(token: SecretString, bytes: usize, encoding: &str) -> Self {
+        Self {
+            length: token.len(),
+            token,
+            bytes,
+            encoding: encoding.to_lowercase(),
+            entropy: token_entropy(bytes),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CheckResponse {
+    /// Left out of the CLI's output unless asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<SecretString>,
+    pub length: usize,
+    pub entropy: f64,
+    pub strength: String,
+    pub breached: Option<u64>,
+    pub score: u8,
+    pub guesses: f64,
+    pub guesses_log10: f64,
+    pub crack_times: Vec<CrackTimeItem>,
+    pub patterns: Vec<PatternItem>,
+    pub warnings: Vec<String>,
+    pub analysis: Vec<AnalysisItem>,
+    /// Rules the password breaks, when checked against password rules.
+    pub rule_violations: Option<Vec<String>>,
+    /// Results for each compliance policy the password was checked against.
+    pub policies: Vec<PolicyResult>,
+}
+
+#[derive(Serialize)]
+pub struct PolicyResult {
+    pub policy: String,
+    pub title: String,
+    pub passed: bool,
+    pub rules: Vec<AnalysisItem>,
+}
+
+#[derive(Serialize)]
+pub struct CrackTimeItem {
+    pub scenario: String,
+    pub seconds: f64,
+    pub display: String,
+}
+
+#[derive(Serialize)]
+pub struct PatternItem {
+    pub pattern: String,
+    pub token: String,
+    pub guesses_log10: f64,
+    pub detail: String,
+}
+
+#[derive(Serialize)]
+pub struct AnalysisItem {
+    pub criterion: String,
+    pub status: bool,
+}
+
+impl CheckResponse {
+    /// Runs the full strength analysis. `breached` is the breach database
+    /// count, if a lookup was made. The password is estimated once and the
+    /// score, warnings and checklist all come from that estimate.
+    pub fn new(password: &str, breached: Option<u64>) -> Self {
+        let estimate = estimate_strength(password);
+        let analysis = analyze_with_estimate(password, &estimate, breached);
+
+        Self {
+            password: Some(SecretString::new(password.to_string())),
+            length: password.len(),
+            entropy: calculate_entropy(password),
+            strength: PasswordStrength::from_score(estimate.score)
+                .to_string()
+                .to_string(),
+            breached,
+            score: estimate.score,
+            guesses: estimate.guesses,
+            guesses_log10: estimate.guesses_log10,
+            crack_times: estimate
+                .crack_times
+                .iter()
+                .map(|crack_time| CrackTimeItem {
+                    scenario: crack_time.scenario.to_string(),
+                    seconds: crack_time.seconds,
+                    display: crack_time.display.clone(),
+                })
+                .collect(),
+            patterns: estimate
+                .sequence
+                .iter()
+                .map(|m| PatternItem {
+                    pattern: m.pattern.to_string(),
+                    token: m.token.clone(),
+                    guesses_log10: m.guesses.log10(),
+                    detail: m.detail.clone(),
+                })
+                .collect(),
+            warnings: estimate.warnings,
+            analysis: analysis
+                .into_iter()
+                .map(|(criterion, status)| AnalysisItem { criterion, status })
+                .collect(),
+            rule_violations: None,
+            policies: Vec::new(),
+        }
+    }
+
+    /// Also checks the password against a site's password rules.
+    pub fn with_rules(mut self, password: &str, rules: &PasswordRules) -> Self {
+        self.rule_violations = Some(rules.violations(password));
+        self
+    }
+
+    /// Also checks the password against a compliance policy.
+    pub fn with_policy(mut self, password: &str, policy: Policy) -> Self {
+        let rules: Vec<AnalysisItem> = policy
+            .evaluate(password, self.breached)
+            .into_iter()
+            .map(|(criterion, status)| AnalysisItem { criterion, status })
+            .collect();
+        self.policies.push(PolicyResult {
+            policy: policy.name().to_string(),
+            title: policy.title().to_string(),
+            passed: rules.iter().all(|rule| rule.status),
+            rules,
+        });
+        self
+    }
+
+    /// True if the password breaks its rules or fails any policy.
+    pub fn is_noncompliant(&self) -> bool {
+        self.rule_violations
+            .as_ref()
+            .is_some_and(|violations| !violations.is_empty())
+            || self.policies.iter().any(|result| !result.passed)
+    }
+}
+
+#[derive(Serialize)]
+pub struct HashResponse {
+    /// Left out of the CLI's output unless asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input: Option<SecretString>,
+    pub algorithm: String,
+    pub hash: String,
+}
+
+#[derive(Serialize)]
+pub struct VerifyResponse {
+    pub scheme: String,
+    pub matches: bool,
+    pub needs_rehash: bool,
+    pub reason: Option<String>,
+}
+
+impl From<Verification> for VerifyResponse {
+    fn from(verification: Verification) -> Self {
+        Self {
+            scheme: verification.scheme,
+            matches: verification.matches,
+            needs_rehash: verification.needs_rehash,
+            reason: verification.reason,
+        }
+    }
+}
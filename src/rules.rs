@@ -0,0 +1,288 @@
+use crate::error::{Error, Result};
+
+/// Most `required` rules accepted; exact entropy takes time exponential in
+/// their number.
+const MAX_REQUIRED_RULES: usize = 12;
+
+/// A set of password rules in the `passwordrules` attribute format used by
+/// Safari and the WHATWG proposal, e.g.
+/// `required: upper; required: digit; allowed: [-_]; minlength: 20; max-consecutive: 2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordRules {
+    /// Each entry must be matched by at least one character: the rule's
+    /// text (for messages) and its characters.
+    required: Vec<(String, Vec<char>)>,
+    /// Every character a password may use: the `allowed` classes plus every
+    /// `required` class, or all of printable ASCII if neither is given.
+    allowed: Vec<char>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    max_consecutive: Option<usize>,
+}
+
+impl PasswordRules {
+    /// Parses a rules string. Property names are case-insensitive, and when a
+    /// length or `max-consecutive` property repeats, the strictest value wins
+    /// as the format specifies. `unicode` is treated as `ascii-printable`.
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut required = Vec::new();
+        let mut allowed: Vec<char> = Vec::new();
+        let mut min_length: Option<usize> = None;
+        let mut max_length: Option<usize> = None;
+        let mut max_consecutive: Option<usize> = None;
+
+        for rule in source
+            .split(';')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+        {
+            let (name, value) = rule.split_once(':').ok_or_else(|| {
+                Error::Rules(format!("Invalid rule '{}': expected 'name: value'", rule))
+            })?;
+            let value = value.trim();
+
+            match name.trim().to_lowercase().as_str() {
+                "required" => {
+                    required.push((value.to_string(), parse_classes(value)?));
+                }
+                "allowed" => {
+                    for c in parse_classes(value)? {
+                        if !allowed.contains(&c) {
+                            allowed.push(c);
+                        }
+                    }
+                }
+                "minlength" => {
+                    let value = parse_number(rule, value)?;
+                    min_length = Some(min_length.map_or(value, |min| min.max(value)));
+                }
+                "maxlength" => {
+                    let value = parse_number(rule, value)?;
+                    max_length = Some(max_length.map_or(value, |max| max.min(value)));
+                }
+                "max-consecutive" => {
+                    let value = parse_number(rule, value)?;
+                    if value == 0 {
+                        return Err(Error::Rules(format!(
+                            "Invalid rule '{}': max-consecutive must be at least 1",
+                            rule
+                        )));
+                    }
+                    max_consecutive = Some(max_consecutive.map_or(value, |max| max.min(value)));
+                }
+                other => {
+                    return Err(Error::Rules(format!(
+                        "Unknown rule '{}' (valid: required, allowed, minlength, maxlength, max-consecutive)",
+                        other
+                    )))
+                }
+            }
+        }
+
+        if required.len() > MAX_REQUIRED_RULES {
+            return Err(Error::Rules(format!(
+                "At most {} required rules are supported",
+                MAX_REQUIRED_RULES
+            )));
+        }
+        if let (Some(min), Some(max)) = (min_length, max_length) {
+            if min > max {
+                return Err(Error::Rules(format!(
+                    "minlength {} is greater than maxlength {}",
+                    min, max
+                )));
+            }
+        }
+
+        if allowed.is_empty() && required.is_empty() {
+            allowed = ascii_printable();
+        }
+        for (_, chars) in &required {
+            for &c in chars {
+                if !allowed.contains(&c) {
+                    allowed.push(c);
+                }
+            }
+        }
+
+        Ok(Self {
+            required,
+            allowed,
+            min_length,
+            max_length,
+            max_consecutive,
+        })
+    }
+
+    pub fn required(&self) -> &[(String, Vec<char>)] {
+        &self.required
+    }
+
+    pub fn allowed(&self) -> &[char] {
+        &self.allowed
+    }
+
+    pub fn max_consecutive(&self) -> Option<usize> {
+        self.max_consecutive
+    }
+
+    /// `preferred`, moved into the rules' length range if it falls outside.
+    pub fn default_length(&self, preferred: usize) -> usize {
+        let length = preferred.max(self.min_length.unwrap_or(0));
+        self.max_length.map_or(length, |max| length.min(max))
+    }
+
+    /// Rejects lengths outside `minlength`..=`maxlength`.
+    pub fn check_length(&self, length: usize) -> Result<()> {
+        if let Some(min) = self.min_length.filter(|&min| length < min) {
+            return Err(Error::Rules(format!(
+                "Length {} is below the rules' minlength of {}",
+                length, min
+            )));
+        }
+        if let Some(max) = self.max_length.filter(|&max| length > max) {
+            return Err(Error::Rules(format!(
+                "Length {} is above the rules' maxlength of {}",
+                length, max
+            )));
+        }
+        Ok(())
+    }
+
+    /// Lists every rule `password` breaks; empty if it satisfies them all.
+    pub fn violations(&self, password: &str) -> Vec<String> {
+        let length = password.chars().count();
+        let mut violations = Vec::new();
+
+        if let Some(min) = self.min_length.filter(|&min| length < min) {
+            violations.push(format!("Shorter than the minimum of {} characters", min));
+        }
+        if let Some(max) = self.max_length.filter(|&max| length > max) {
+            violations.push(format!("Longer than the maximum of {} characters", max));
+        }
+        for (rule, chars) in &self.required {
+            if !password.chars().any(|c| chars.contains(&c)) {
+                violations.push(format!("Missing a required character: {}", rule));
+            }
+        }
+
+        let mut disallowed: Vec<char> = Vec::new();
+        for c in password.chars() {
+            if !self.allowed.contains(&c) && !disallowed.contains(&c) {
+                disallowed.push(c);
+            }
+        }
+        if !disallowed.is_empty() {
+            violations.push(format!(
+                "Uses characters the rules don't allow: {}",
+                disallowed.into_iter().collect::<String>()
+            ));
+        }
+
+        if let Some(max) = self.max_consecutive {
+            if longest_run(password) > max {
+                violations.push(format!(
+                    "Repeats a character more than {} times in a row",
+                    max
+                ));
+            }
+        }
+
+        violations
+    }
+}
+
+/// Length of the longest run of one repeated character.
+pub fn longest_run(password: &str) -> usize {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous = None;
+    for c in password.chars() {
+        run = if previous == Some(c) { run + 1 } else { 1 };
+        longest = longest.max(run);
+        previous = Some(c);
+    }
+    longest
+}
+
+fn parse_number(rule: &str, value: &str) -> Result<usize> {
+    value
+        .parse()
+        .map_err(|_| Error::Rules(format!("Invalid rule '{}': expected a number", rule)))
+}
+
+fn ascii_printable() -> Vec<char> {
+    (' '..='~').collect()
+}
+
+/// Parses a comma-separated list of named classes and `[...]` custom
+/// classes into the characters they cover.
+fn parse_classes(value: &str) -> Result<Vec<char>> {
+    let mut chars: Vec<char> = Vec::new();
+    let mut add = |set: Vec<char>| {
+        for c in set {
+            if !chars.contains(&c) {
+                chars.push(c);
+            }
+        }
+    };
+
+    let mut rest = value.trim();
+    while !rest.is_empty() {
+        if let Some(body) = rest.strip_prefix('[') {
+            // A ']' directly after '[' is part of the class.
+            let skip = usize::from(body.starts_with(']'));
+            let close = body[skip..]
+                .find(']')
+                .map(|i| i + skip)
+                .ok_or_else(|| Error::Rules(format!("Unterminated custom class in '{}'", value)))?;
+            let class: Vec<char> = body[..close].chars().collect();
+            if let Some(bad) = class.iter().find(|c| !(' '..='~').contains(*c)) {
+                return Err(Error::Rules(format!(
+                    "Custom classes may only contain printable ASCII, found '{}'",
+                    bad
+                )));
+            }
+            add(class);
+            rest = &body[close + 1..];
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            let name = rest[..end].trim();
+            add(match name.to_lowercase().as_str() {
+                "upper" => ('A'..='Z').collect(),
+                "lower" => ('a'..='z').collect(),
+                "digit" => ('0'..='9').collect(),
+                "special" => ascii_printable()
+                    .into_iter()
+                    .filter(|c| !c.is_ascii_alphanumeric())
+                    .collect(),
+                "ascii-printable" | "unicode" => ascii_printable(),
+                _ => {
+                    return Err(Error::Rules(format!(
+                        "Unknown character class '{}' (valid: upper, lower, digit, special, ascii-printable, unicode, or [custom])",
+                        name
+                    )))
+                }
+            });
+            rest = &rest[end..];
+        }
+
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix(',') {
+            rest = after.trim_start();
+        } else if !rest.is_empty() {
+            return Err(Error::Rules(format!(
+                "Expected ',' between character classes in '{}'",
+                value
+            )));
+        }
+    }
+
+    if chars.is_empty() {
+        return Err(Error::Rules(format!(
+            "No character classes given in '{}'",
+            value
+        )));
+    }
+    Ok(chars)
+}
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::hash::{Hash, Hasher};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+/// A password or other secret. Its buffer is wiped when it is dropped and,
+/// with the `mlock` feature on Linux, kept out of swap while it lives.
+///
+/// It has no `Debug` or `Display` so it can't end up in logs or error
+/// messages by accident; `expose` reads it where it is really needed.
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(secret: String) -> Self {
+        lock(&secret);
+        Self(secret)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Default for SecretString {
+    fn default() -> Self {
+        Self::new(String::new())
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(secret: String) -> Self {
+        Self::new(secret)
+    }
+}
+
+impl FromIterator<char> for SecretString {
+    /// Reserves room for the iterator's length up front, so growing the
+    /// buffer doesn't leave partial copies behind in freed memory.
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut secret = String::with_capacity(iter.size_hint().0 * 4);
+        secret.extend(iter);
+        Self::new(secret)
+    }
+}
+
+impl Clone for SecretString {
+    fn clone(&self) -> Self {
+        Self::new(self.0.clone())
+    }
+}
+
+impl PartialEq for SecretString {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_bytes().ct_eq(other.0.as_bytes()).into()
+    }
+}
+
+impl Eq for SecretString {}
+
+impl Hash for SecretString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl Serialize for SecretString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::new)
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        unlock(&self.0);
+        self.0.zeroize();
+    }
+}
+
+/// Best effort: failing to lock (e.g. past `RLIMIT_MEMLOCK`) leaves the
+/// buffer swappable but otherwise usable.
+#[cfg(all(feature = "mlock", target_os = "linux"))]
+fn lock(buffer: &String) {
+    if buffer.capacity() > 0 {
+        // SAFETY: the range is the string's own live allocation.
+        unsafe {
+            libc::mlock(buffer.as_ptr().cast(), buffer.capacity());
+        }
+    }
+}
+
+#[cfg(all(feature = "mlock", target_os = "linux"))]
+fn unlock(buffer: &String) {
+    if buffer.capacity() > 0 {
+        // SAFETY: as in `lock`.
+        unsafe {
+            libc::munlock(buffer.as_ptr().cast(), buffer.capacity());
+        }
+    }
+}
+
+#[cfg(not(all(feature = "mlock", target_os = "linux")))]
+fn lock(_buffer: &String) {}
+
+#[cfg(not(all(feature = "mlock", target_os = "linux")))]
+fn unlock(_buffer: &String) {}
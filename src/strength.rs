@@ -0,0 +1,911 @@
+//! Guess-based password strength estimation in the style of zxcvbn.
+//!
+//! The password is scanned for patterns an attacker would try first
+//! (dictionary words, l33t spellings, keyboard walks, dates, repeats and
+//! sequences). Each match gets a guess count, and the cheapest way to cover
+//! the whole password with matches and brute-forced gaps gives the estimate.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// zxcvbn's frequency-ranked lists, most common first (MIT; see
+// src/data/README.md)
+const COMMON_PASSWORDS: &str = include_str!("data/common_passwords.txt");
+const ENGLISH_WORDS: &str = include_str!("data/english_words.txt");
+const TV_AND_FILM_WORDS: &str = include_str!("data/tv_and_film_words.txt");
+const SURNAMES: &str = include_str!("data/surnames.txt");
+const FEMALE_NAMES: &str = include_str!("data/female_names.txt");
+const MALE_NAMES: &str = include_str!("data/male_names.txt");
+
+/// Only this many leading characters are pattern-matched; the rest of a very
+/// long password is already far beyond any practical attack.
+const MAX_ANALYZED_CHARS: usize = 256;
+
+const BRUTEFORCE_CARDINALITY: f64 = 10.0;
+const MIN_GUESSES_BEFORE_GROWING_SEQUENCE: f64 = 10_000.0;
+const MIN_SUBMATCH_GUESSES_SINGLE_CHAR: f64 = 10.0;
+const MIN_SUBMATCH_GUESSES_MULTI_CHAR: f64 = 50.0;
+const MIN_YEAR_SPACE: f64 = 20.0;
+
+/// QWERTY rows, unshifted and shifted. Each row is offset half a key to the
+/// right of the one above it.
+const KEYBOARD_ROWS: [(&str, &str); 4] = [
+    ("`1234567890-=", "~!@#$%^&*()_+"),
+    ("qwertyuiop[]\\", "QWERTYUIOP{}|"),
+    ("asdfghjkl;'", "ASDFGHJKL:\""),
+    ("zxcvbnm,./", "ZXCVBNM<>?"),
+];
+const KEYBOARD_STARTING_POSITIONS: f64 = 94.0;
+const KEYBOARD_AVERAGE_DEGREE: f64 = 4.6;
+
+const L33T_TABLE: &[(char, &[char])] = &[
+    ('4', &['a']),
+    ('@', &['a']),
+    ('8', &['b']),
+    ('(', &['c']),
+    ('3', &['e']),
+    ('6', &['g']),
+    ('1', &['i', 'l']),
+    ('!', &['i']),
+    ('|', &['i', 'l']),
+    ('0', &['o']),
+    ('$', &['s']),
+    ('5', &['s']),
+    ('7', &['t']),
+    ('+', &['t']),
+    ('2', &['z']),
+];
+
+/// A pattern found in the password, covering characters `i..=j`.
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub pattern: &'static str,
+    pub i: usize,
+    pub j: usize,
+    pub token: String,
+    pub guesses: f64,
+    /// Human-readable explanation, e.g. which dictionary word was found.
+    pub detail: String,
+}
+
+/// Estimated time to crack under one attack model.
+#[derive(Debug, Clone)]
+pub struct CrackTime {
+    pub scenario: &'static str,
+    pub seconds: f64,
+    pub display: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct StrengthEstimate {
+    pub guesses: f64,
+    pub guesses_log10: f64,
+    /// 0 (too guessable) to 4 (very unguessable), as in zxcvbn.
+    pub score: u8,
+    pub crack_times: Vec<CrackTime>,
+    /// The cheapest sequence of matches covering the password.
+    pub sequence: Vec<Match>,
+    pub warnings: Vec<String>,
+}
+
+impl StrengthEstimate {
+    /// True when the cheapest decomposition is pure brute force, i.e. no
+    /// dictionary word or predictable pattern made the password easier.
+    pub fn is_patternless(&self) -> bool {
+        self.sequence.iter().all(|m| m.pattern == "bruteforce")
+    }
+}
+
+/// Attack models, as guesses per second.
+const ATTACK_SCENARIOS: [(&str, f64); 4] = [
+    ("Online attack, throttled (100/hour)", 100.0 / 3600.0),
+    ("Online attack, unthrottled (10/second)", 10.0),
+    ("Offline attack, slow hash (10k/second)", 1e4),
+    ("Offline attack, fast hash (10B/second)", 1e10),
+];
+
+pub fn estimate_strength(password: &str) -> StrengthEstimate {
+    let chars: Vec<char> = password.chars().take(MAX_ANALYZED_CHARS).collect();
+    let (guesses_log10, sequence) = most_guessable_sequence(&chars);
+    let guesses = 10f64.powf(guesses_log10);
+
+    let score = match guesses {
+        g if g < 1e3 + 5.0 => 0,
+        g if g < 1e6 + 5.0 => 1,
+        g if g < 1e8 + 5.0 => 2,
+        g if g < 1e10 + 5.0 => 3,
+        _ => 4,
+    };
+
+    let crack_times = ATTACK_SCENARIOS
+        .iter()
+        .map(|&(scenario, rate)| {
+            let seconds = guesses / rate;
+            CrackTime {
+                scenario,
+                seconds,
+                display: display_time(seconds),
+            }
+        })
+        .collect();
+
+    let warnings = sequence.iter().filter_map(warning_for).collect();
+
+    StrengthEstimate {
+        guesses,
+        guesses_log10,
+        score,
+        crack_times,
+        sequence,
+        warnings,
+    }
+}
+
+fn warning_for(m: &Match) -> Option<String> {
+    match m.pattern {
+        "dictionary" => Some(format!("\"{}\" is {}", m.token, m.detail)),
+        "reversed" | "l33t" => Some(format!(
+            "\"{}\" is {}; such substitutions don't help much",
+            m.token, m.detail
+        )),
+        "spatial" => Some(format!("\"{}\" is a keyboard pattern", m.token)),
+        "repeat" => Some(format!("\"{}\" repeats characters", m.token)),
+        "sequence" => Some(format!("\"{}\" is an easy-to-guess sequence", m.token)),
+        "date" => Some(format!("\"{}\" looks like a date or year", m.token)),
+        _ => None,
+    }
+}
+
+fn display_time(seconds: f64) -> String {
+    const MINUTE: f64 = 60.0;
+    const HOUR: f64 = MINUTE * 60.0;
+    const DAY: f64 = HOUR * 24.0;
+    const MONTH: f64 = DAY * 31.0;
+    const YEAR: f64 = MONTH * 12.0;
+    const CENTURY: f64 = YEAR * 100.0;
+
+    let (amount, unit) = match seconds {
+        s if s < 1.0 => return "less than a second".to_string(),
+        s if s < MINUTE => (s, "second"),
+        s if s < HOUR => (s / MINUTE, "minute"),
+        s if s < DAY => (s / HOUR, "hour"),
+        s if s < MONTH => (s / DAY, "day"),
+        s if s < YEAR => (s / MONTH, "month"),
+        s if s < CENTURY => (s / YEAR, "year"),
+        _ => return "centuries".to_string(),
+    };
+    let amount = amount.round() as u64;
+    format!("{} {}{}", amount, unit, if amount == 1 { "" } else { "s" })
+}
+
+// --- Matching ---------------------------------------------------------------
+
+fn omnimatch(chars: &[char]) -> Vec<Match> {
+    let mut matches = Vec::new();
+    matches.extend(dictionary_matches(chars));
+    matches.extend(reversed_dictionary_matches(chars));
+    matches.extend(l33t_matches(chars));
+    matches.extend(spatial_matches(chars));
+    matches.extend(repeat_matches(chars));
+    matches.extend(sequence_matches(chars));
+    matches.extend(date_matches(chars));
+    matches
+}
+
+/// Ranked dictionaries: a word's guess count is its rank in the list.
+fn ranked_dictionaries() -> &'static [(&'static str, HashMap<&'static str, usize>)] {
+    static DICTIONARIES: OnceLock<Vec<(&'static str, HashMap<&'static str, usize>)>> =
+        OnceLock::new();
+    DICTIONARIES.get_or_init(|| {
+        [
+            ("common password", COMMON_PASSWORDS),
+            ("English word", ENGLISH_WORDS),
+            ("TV and film word", TV_AND_FILM_WORDS),
+            ("surname", SURNAMES),
+            ("female name", FEMALE_NAMES),
+            ("male name", MALE_NAMES),
+        ]
+        .into_iter()
+        .map(|(name, list)| {
+            let mut ranked = HashMap::new();
+            for (rank, word) in list.lines().map(str::trim).enumerate() {
+                ranked.entry(word).or_insert(rank + 1);
+            }
+            (name, ranked)
+        })
+        .collect()
+    })
+}
+
+/// True if `dictionary` (`"common password"` or `"English word"`) lists
+/// the whole password, ignoring case.
+pub fn in_dictionary(password: &str, dictionary: &str) -> bool {
+    ranked_dictionaries().iter().any(|(name, ranked)| {
+        *name == dictionary && ranked.contains_key(password.to_lowercase().as_str())
+    })
+}
+
+/// Finds the best-ranked dictionary entry for a lowercase word.
+fn lookup(word: &str) -> Option<(&'static str, usize)> {
+    ranked_dictionaries()
+        .iter()
+        .filter_map(|(name, ranked)| ranked.get(word).map(|&rank| (*name, rank)))
+        .min_by_key(|&(_, rank)| rank)
+}
+
+fn dictionary_matches(chars: &[char]) -> Vec<Match> {
+    let lower: Vec<char> = chars.iter().map(|c| c.to_ascii_lowercase()).collect();
+    let mut matches = Vec::new();
+    for i in 0..chars.len() {
+        for j in i + 2..chars.len() {
+            let word: String = lower[i..=j].iter().collect();
+            if let Some((dictionary, rank)) = lookup(&word) {
+                let token: String = chars[i..=j].iter().collect();
+                matches.push(Match {
+                    pattern: "dictionary",
+                    i,
+                    j,
+                    guesses: rank as f64 * uppercase_variations(&token),
+                    detail: format!("{} #{}", dictionary, rank),
+                    token,
+                });
+            }
+        }
+    }
+    matches
+}
+
+fn reversed_dictionary_matches(chars: &[char]) -> Vec<Match> {
+    let reversed: Vec<char> = chars.iter().rev().cloned().collect();
+    let n = chars.len();
+    dictionary_matches(&reversed)
+        .into_iter()
+        .filter(|m| m.token.chars().rev().collect::<String>() != m.token)
+        .map(|m| Match {
+            pattern: "reversed",
+            i: n - 1 - m.j,
+            j: n - 1 - m.i,
+            token: m.token.chars().rev().collect(),
+            guesses: m.guesses * 2.0,
+            detail: format!("a reversed {}", m.detail),
+        })
+        .collect()
+}
+
+fn l33t_matches(chars: &[char]) -> Vec<Match> {
+    let l33t: HashMap<char, &[char]> = L33T_TABLE.iter().cloned().collect();
+    let mut matches = Vec::new();
+
+    for i in 0..chars.len() {
+        for j in i + 2..chars.len().min(i + 24) {
+            let token = &chars[i..=j];
+            if !token.iter().any(|c| l33t.contains_key(c)) {
+                continue;
+            }
+
+            // Expand every ambiguous substitution ('1' could be i or l)
+            let mut candidates: Vec<(String, Vec<(char, char)>)> =
+                vec![(String::new(), Vec::new())];
+            for &c in token {
+                match l33t.get(&c) {
+                    Some(letters) => {
+                        candidates = candidates
+                            .into_iter()
+                            .flat_map(|(word, subs)| {
+                                letters.iter().map(move |&letter| {
+                                    let mut word = word.clone();
+                                    let mut subs = subs.clone();
+                                    word.push(letter);
+                                    subs.push((c, letter));
+                                    (word, subs)
+                                })
+                            })
+                            .collect();
+                    }
+                    None => {
+                        for (word, _) in candidates.iter_mut() {
+                            word.push(c.to_ascii_lowercase());
+                        }
+                    }
+                }
+                if candidates.len() > 64 {
+                    break;
+                }
+            }
+
+            let token: String = token.iter().collect();
+            let best = candidates
+                .into_iter()
+                .filter(|(word, _)| word.chars().count() == token.chars().count())
+                .filter_map(|(word, subs)| lookup(&word).map(|found| (word, subs, found)))
+                .min_by_key(|(_, _, (_, rank))| *rank);
+            if let Some((word, subs, (dictionary, rank))) = best {
+                matches.push(Match {
+                    pattern: "l33t",
+                    i,
+                    j,
+                    guesses: rank as f64
+                        * uppercase_variations(&token)
+                        * l33t_variations(&token, &subs),
+                    detail: format!("a l33t spelling of {} #{} \"{}\"", dictionary, rank, word),
+                    token,
+                });
+            }
+        }
+    }
+
+    matches
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// How many capitalisations of the word an attacker would have to try.
+fn uppercase_variations(token: &str) -> f64 {
+    let upper = token.chars().filter(|c| c.is_ascii_uppercase()).count();
+    let lower = token.chars().filter(|c| c.is_ascii_lowercase()).count();
+    if upper == 0 {
+        return 1.0;
+    }
+    let first_upper = token.chars().next().is_some_and(|c| c.is_ascii_uppercase());
+    let last_upper = token.chars().last().is_some_and(|c| c.is_ascii_uppercase());
+    if lower == 0 || (upper == 1 && (first_upper || last_upper)) {
+        return 2.0;
+    }
+    (1..=upper.min(lower))
+        .map(|i| binomial(upper + lower, i))
+        .sum()
+}
+
+fn l33t_variations(token: &str, subs: &[(char, char)]) -> f64 {
+    let mut variations = 1.0;
+    let mut seen: Vec<(char, char)> = Vec::new();
+    for &(subbed, letter) in subs {
+        if seen.contains(&(subbed, letter)) {
+            continue;
+        }
+        seen.push((subbed, letter));
+        let s = token.chars().filter(|&c| c == subbed).count();
+        let u = token
+            .chars()
+            .filter(|c| c.to_ascii_lowercase() == letter)
+            .count();
+        if s == 0 || u == 0 {
+            variations *= 2.0;
+        } else {
+            variations *= (1..=s.min(u)).map(|i| binomial(s + u, i)).sum::<f64>();
+        }
+    }
+    variations
+}
+
+/// Returns `(row, column, shifted)` for a key on the QWERTY layout.
+fn key_position(c: char) -> Option<(i32, i32, bool)> {
+    KEYBOARD_ROWS
+        .iter()
+        .enumerate()
+        .find_map(|(row, (plain, shifted))| {
+            if let Some(col) = plain.chars().position(|k| k == c) {
+                Some((row as i32, col as i32, false))
+            } else {
+                shifted
+                    .chars()
+                    .position(|k| k == c)
+                    .map(|col| (row as i32, col as i32, true))
+            }
+        })
+}
+
+/// Direction from one key to an adjacent one, or `None` if not adjacent.
+fn key_direction(from: (i32, i32), to: (i32, i32)) -> Option<u8> {
+    let (dr, dc) = (to.0 - from.0, to.1 - from.1);
+    match (dr, dc) {
+        (0, -1) => Some(0),
+        (0, 1) => Some(1),
+        (-1, 0) => Some(2),
+        (-1, 1) => Some(3),
+        (1, -1) => Some(4),
+        (1, 0) => Some(5),
+        _ => None,
+    }
+}
+
+fn spatial_matches(chars: &[char]) -> Vec<Match> {
+    let mut matches = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let mut j = i;
+        let mut turns = 0;
+        let mut shifted = 0;
+        let mut last_direction = None;
+        if let Some((_, _, true)) = key_position(chars[i]) {
+            shifted += 1;
+        }
+
+        while j + 1 < chars.len() {
+            let (Some(from), Some(to)) = (key_position(chars[j]), key_position(chars[j + 1]))
+            else {
+                break;
+            };
+            let Some(direction) = key_direction((from.0, from.1), (to.0, to.1)) else {
+                break;
+            };
+            if last_direction != Some(direction) {
+                turns += 1;
+                last_direction = Some(direction);
+            }
+            if to.2 {
+                shifted += 1;
+            }
+            j += 1;
+        }
+
+        if j - i + 1 >= 3 {
+            let token: String = chars[i..=j].iter().collect();
+            matches.push(Match {
+                pattern: "spatial",
+                i,
+                j,
+                guesses: spatial_guesses(j - i + 1, turns, shifted),
+                detail: format!("a keyboard walk with {} turn(s)", turns),
+                token,
+            });
+            i = j;
+        } else {
+            i += 1;
+        }
+    }
+    matches
+}
+
+fn spatial_guesses(length: usize, turns: usize, shifted: usize) -> f64 {
+    let mut guesses = 0.0;
+    for i in 2..=length {
+        for j in 1..=turns.min(i - 1) {
+            guesses += binomial(i - 1, j - 1)
+                * KEYBOARD_STARTING_POSITIONS
+                * KEYBOARD_AVERAGE_DEGREE.powi(j as i32);
+        }
+    }
+    if shifted > 0 {
+        let unshifted = length - shifted;
+        if unshifted == 0 {
+            guesses *= 2.0;
+        } else {
+            guesses *= (1..=shifted.min(unshifted))
+                .map(|i| binomial(shifted + unshifted, i))
+                .sum::<f64>();
+        }
+    }
+    guesses
+}
+
+fn repeat_matches(chars: &[char]) -> Vec<Match> {
+    let mut matches = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        // Prefer the repeat covering the most characters, then the shortest base
+        let mut best: Option<(usize, usize)> = None;
+        for base_len in 1..=(chars.len() - i) / 2 {
+            let base = &chars[i..i + base_len];
+            let mut count = 1;
+            while i + (count + 1) * base_len <= chars.len()
+                && &chars[i + count * base_len..i + (count + 1) * base_len] == base
+            {
+                count += 1;
+            }
+            let covered = count * base_len;
+            if count >= 2 && covered >= 3 && best.is_none_or(|(_, c)| covered > c) {
+                best = Some((base_len, covered));
+            }
+        }
+
+        match best {
+            Some((base_len, covered)) => {
+                let base = &chars[i..i + base_len];
+                let base_guesses = 10f64.powf(most_guessable_sequence(base).0);
+                let count = covered / base_len;
+                let j = i + covered - 1;
+                matches.push(Match {
+                    pattern: "repeat",
+                    i,
+                    j,
+                    token: chars[i..=j].iter().collect(),
+                    guesses: base_guesses * count as f64,
+                    detail: format!(
+                        "\"{}\" repeated {} times",
+                        base.iter().collect::<String>(),
+                        count
+                    ),
+                });
+                i = j + 1;
+            }
+            None => i += 1,
+        }
+    }
+    matches
+}
+
+fn sequence_matches(chars: &[char]) -> Vec<Match> {
+    let mut matches = Vec::new();
+    let mut push = |i: usize, j: usize, delta: i64| {
+        if j - i + 1 < 3 || delta == 0 || delta.abs() > 5 {
+            return;
+        }
+        let token: String = chars[i..=j].iter().collect();
+        let first = chars[i];
+        let mut base: f64 = if "aAzZ019".contains(first) {
+            4.0
+        } else if first.is_ascii_digit() {
+            10.0
+        } else {
+            26.0
+        };
+        if delta < 0 {
+            base *= 2.0;
+        }
+        matches.push(Match {
+            pattern: "sequence",
+            i,
+            j,
+            guesses: base * (j - i + 1) as f64,
+            detail: format!("a sequence with step {}", delta),
+            token,
+        });
+    };
+
+    if chars.len() < 3 {
+        return matches;
+    }
+    let mut i = 0;
+    let mut last_delta = chars[1] as i64 - chars[0] as i64;
+    for k in 1..chars.len() - 1 {
+        let delta = chars[k + 1] as i64 - chars[k] as i64;
+        if delta != last_delta {
+            push(i, k, last_delta);
+            i = k;
+            last_delta = delta;
+        }
+    }
+    push(i, chars.len() - 1, last_delta);
+
+    matches
+}
+
+fn reference_year() -> i64 {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    1970 + (seconds / 31_556_952) as i64
+}
+
+fn year_space(year: i64) -> f64 {
+    ((year - reference_year()).abs() as f64).max(MIN_YEAR_SPACE)
+}
+
+fn expand_year(year: i64, digits: usize) -> i64 {
+    match (digits, year) {
+        (2, y) if y > 50 => 1900 + y,
+        (2, y) => 2000 + y,
+        (_, y) => y,
+    }
+}
+
+/// Interprets `parts` (numeric strings) as a day, month and year in any
+/// common ordering.
+fn parse_date(parts: &[&str]) -> Option<i64> {
+    let numbers: Vec<i64> = parts
+        .iter()
+        .map(|p| p.parse().ok())
+        .collect::<Option<_>>()?;
+    let orders: [[usize; 3]; 3] = [[2, 1, 0], [2, 0, 1], [0, 1, 2]]; // (year, month, day) indices
+    for [y, m, d] in orders {
+        if parts[y].len() != 2 && parts[y].len() != 4 {
+            continue;
+        }
+        let year = expand_year(numbers[y], parts[y].len());
+        let month = numbers[m];
+        let day = numbers[d];
+        if (1000..=2050).contains(&year) && (1..=12).contains(&month) && (1..=31).contains(&day) {
+            return Some(year);
+        }
+    }
+    None
+}
+
+fn date_matches(chars: &[char]) -> Vec<Match> {
+    let mut matches = Vec::new();
+    let n = chars.len();
+
+    for i in 0..n {
+        for j in i + 3..n.min(i + 10) {
+            let token: String = chars[i..=j].iter().collect();
+            let len = j - i + 1;
+
+            if token.chars().all(|c| c.is_ascii_digit()) {
+                if len == 4 {
+                    let year: i64 = token.parse().unwrap_or(0);
+                    if (1900..=2050).contains(&year) {
+                        matches.push(Match {
+                            pattern: "date",
+                            i,
+                            j,
+                            guesses: year_space(year),
+                            detail: "a recent year".to_string(),
+                            token: token.clone(),
+                        });
+                    }
+                }
+                if !(4..=8).contains(&len) {
+                    continue;
+                }
+                // Try every split of the digits into three date parts
+                let best = (1..len.min(5))
+                    .flat_map(|a| (a + 1..len.min(a + 5)).map(move |b| (a, b)))
+                    .filter(|&(_, b)| len - b <= 4)
+                    .filter_map(|(a, b)| parse_date(&[&token[..a], &token[a..b], &token[b..]]))
+                    .map(year_space)
+                    .fold(f64::INFINITY, f64::min);
+                if best.is_finite() {
+                    matches.push(Match {
+                        pattern: "date",
+                        i,
+                        j,
+                        guesses: best * 365.0,
+                        detail: "a date".to_string(),
+                        token,
+                    });
+                }
+            } else if (6..=10).contains(&len) {
+                let separator = token.chars().find(|c| !c.is_ascii_digit());
+                let Some(separator) = separator.filter(|c| " -/\\_.".contains(*c)) else {
+                    continue;
+                };
+                let parts: Vec<&str> = token.split(separator).collect();
+                if parts.len() != 3 || parts.iter().any(|p| p.is_empty() || p.len() > 4) {
+                    continue;
+                }
+                if let Some(year) = parse_date(&parts) {
+                    matches.push(Match {
+                        pattern: "date",
+                        i,
+                        j,
+                        guesses: year_space(year) * 365.0 * 4.0,
+                        detail: "a date".to_string(),
+                        token,
+                    });
+                }
+            }
+        }
+    }
+
+    matches
+}
+
+// --- Scoring ----------------------------------------------------------------
+
+fn log10_add(a: f64, b: f64) -> f64 {
+    let (high, low) = if a > b { (a, b) } else { (b, a) };
+    high + (1.0 + 10f64.powf(low - high)).log10()
+}
+
+fn log10_factorial(n: usize) -> f64 {
+    (2..=n).map(|k| (k as f64).log10()).sum()
+}
+
+#[derive(Clone)]
+struct Candidate {
+    /// log10 of the product of match guesses in the sequence.
+    log_product: f64,
+    /// log10 of the total guesses for the sequence.
+    log_guesses: f64,
+    /// Index into `matches` for the last match in the sequence.
+    last: usize,
+}
+
+/// Finds the sequence of non-overlapping matches (with brute-forced gaps)
+/// that covers the password with the fewest guesses, returning `log10` of
+/// that guess count alongside the sequence.
+///
+/// Follows zxcvbn: a sequence of `l` matches costs
+/// `l! * prod(guesses) + D^(l - 1)`, which penalises sequences built from
+/// many small matches.
+fn most_guessable_sequence(chars: &[char]) -> (f64, Vec<Match>) {
+    let n = chars.len();
+    if n == 0 {
+        return (0.0, Vec::new());
+    }
+
+    let mut matches = omnimatch(chars);
+    for m in matches.iter_mut() {
+        let min_guesses = if m.j - m.i + 1 < n {
+            if m.i == m.j {
+                MIN_SUBMATCH_GUESSES_SINGLE_CHAR
+            } else {
+                MIN_SUBMATCH_GUESSES_MULTI_CHAR
+            }
+        } else {
+            1.0
+        };
+        m.guesses = m.guesses.max(min_guesses);
+    }
+
+    // optimal[k][l]: best sequence of l matches covering chars 0..=k
+    let mut optimal: Vec<HashMap<usize, Candidate>> = vec![HashMap::new(); n];
+
+    let update = |optimal: &mut Vec<HashMap<usize, Candidate>>,
+                  matches: &Vec<Match>,
+                  index: usize,
+                  l: usize| {
+        let m = &matches[index];
+        let mut log_product = m.guesses.log10();
+        if l > 1 {
+            log_product += optimal[m.i - 1][&(l - 1)].log_product;
+        }
+        let log_guesses = log10_add(
+            log10_factorial(l) + log_product,
+            (l - 1) as f64 * MIN_GUESSES_BEFORE_GROWING_SEQUENCE.log10(),
+        );
+        let dominated = optimal[m.j]
+            .iter()
+            .any(|(&other_l, other)| other_l <= l && other.log_guesses <= log_guesses);
+        if !dominated {
+            optimal[m.j].insert(
+                l,
+                Candidate {
+                    log_product,
+                    log_guesses,
+                    last: index,
+                },
+            );
+        }
+    };
+
+    let mut by_end: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for (index, m) in matches.iter().enumerate() {
+        by_end[m.j].push(index);
+    }
+
+    for (k, ending_here) in by_end.iter().enumerate() {
+        for &index in ending_here {
+            let i = matches[index].i;
+            if i > 0 {
+                let lengths: Vec<usize> = optimal[i - 1].keys().cloned().collect();
+                for l in lengths {
+                    update(&mut optimal, &matches, index, l + 1);
+                }
+            } else {
+                update(&mut optimal, &matches, index, 1);
+            }
+        }
+
+        // Brute-force the tail ending at k, never chaining two brute-force runs
+        matches.push(bruteforce_match(chars, 0, k));
+        update(&mut optimal, &matches, matches.len() - 1, 1);
+        for i in 1..=k {
+            let previous: Vec<(usize, usize)> = optimal[i - 1]
+                .iter()
+                .map(|(&l, candidate)| (l, candidate.last))
+                .collect();
+            let mut pushed = None;
+            for (l, last) in previous {
+                if matches[last].pattern == "bruteforce" {
+                    continue;
+                }
+                let index = *pushed.get_or_insert_with(|| {
+                    matches.push(bruteforce_match(chars, i, k));
+                    matches.len() - 1
+                });
+                update(&mut optimal, &matches, index, l + 1);
+            }
+        }
+    }
+
+    let (&best_l, best) = optimal[n - 1]
+        .iter()
+        .min_by(|a, b| a.1.log_guesses.total_cmp(&b.1.log_guesses))
+        .expect("brute force always covers the password");
+    let log_guesses = best.log_guesses;
+
+    let mut sequence = Vec::with_capacity(best_l);
+    let mut k = n as isize - 1;
+    let mut l = best_l;
+    while k >= 0 {
+        let candidate = &optimal[k as usize][&l];
+        let m = matches[candidate.last].clone();
+        k = m.i as isize - 1;
+        l -= 1;
+        sequence.push(m);
+    }
+    sequence.reverse();
+
+    (log_guesses, sequence)
+}
+
+fn bruteforce_match(chars: &[char], i: usize, j: usize) -> Match {
+    let length = (j - i + 1) as i32;
+    let min_guesses = if length == 1 {
+        MIN_SUBMATCH_GUESSES_SINGLE_CHAR + 1.0
+    } else {
+        MIN_SUBMATCH_GUESSES_MULTI_CHAR + 1.0
+    };
+    Match {
+        pattern: "bruteforce",
+        i,
+        j,
+        token: chars[i..=j].iter().collect(),
+        guesses: BRUTEFORCE_CARDINALITY.powi(length).max(min_guesses),
+        detail: "no recognisable pattern".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(password, guesses_log10, score)` as computed by the reference zxcvbn.
+    const REFERENCE: &[(&str, f64, u8)] = &[
+        ("correcthorsebatterystaple", 14.44, 4),
+        ("P@ssw0rd", 1.23, 0),
+        ("jennifer1990", 4.18, 1),
+        ("qwertyuiop", 1.36, 0),
+        ("smith1234", 4.18, 1),
+        ("iloveyou", 1.68, 0),
+        ("Monkey99!", 5.04, 1),
+        ("dragonfly2020", 5.82, 1),
+        ("horsestaple", 6.96, 2),
+        ("batterycorrect", 6.70, 2),
+    ];
+
+    #[test]
+    fn estimates_match_reference_zxcvbn() {
+        for &(password, guesses_log10, score) in REFERENCE {
+            let estimate = estimate_strength(password);
+            assert!(
+                (estimate.guesses_log10 - guesses_log10).abs() < 0.01,
+                "{}: 10^{:.2} guesses, expected 10^{:.2}",
+                password,
+                estimate.guesses_log10,
+                guesses_log10
+            );
+            assert_eq!(estimate.score, score, "{}", password);
+        }
+    }
+
+    #[test]
+    fn passphrase_of_dictionary_words_is_not_brute_forced() {
+        let estimate = estimate_strength("correcthorsebatterystaple");
+        let tokens: Vec<(&str, &str)> = estimate
+            .sequence
+            .iter()
+            .map(|m| (m.pattern, m.token.as_str()))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                ("dictionary", "correct"),
+                ("dictionary", "horse"),
+                ("dictionary", "battery"),
+                ("dictionary", "staple"),
+            ]
+        );
+        assert!(!estimate.is_patternless());
+    }
+
+    #[test]
+    fn dictionaries_hold_the_full_ranked_lists() {
+        assert!(in_dictionary("trustno1", "common password"));
+        assert!(in_dictionary("Battery", "English word"));
+        assert_eq!(lookup("password"), Some(("common password", 2)));
+        assert_eq!(lookup("smith"), Some(("surname", 1)));
+        assert!(ranked_dictionaries()
+            .iter()
+            .all(|(_, ranked)| ranked.len() > 900));
+    }
+}
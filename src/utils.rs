@@ -1,62 +1,16 @@
-use anyhow::Result;
 use base64::{engine::general_purpose, Engine as _};
-use hex;
 use rand::Rng;
+use rand_core::{OsRng, RngCore};
 use sha2::{Digest, Sha256, Sha512};
-use std::process::Command;
-
-// Common word list for passphrases
-const COMMON_WORDS: &[&str] = &[
-    "apple",
-    "banana",
-    "cherry",
-    "dragon",
-    "eagle",
-    "forest",
-    "garden",
-    "house",
-    "island",
-    "jungle",
-    "knight",
-    "lemon",
-    "mountain",
-    "ocean",
-    "planet",
-    "queen",
-    "river",
-    "sunset",
-    "tiger",
-    "umbrella",
-    "village",
-    "window",
-    "yellow",
-    "zebra",
-    "anchor",
-    "bridge",
-    "castle",
-    "diamond",
-    "elephant",
-    "flower",
-    "guitar",
-    "hammer",
-    "iceberg",
-    "jacket",
-    "kangaroo",
-    "lighthouse",
-    "moonlight",
-    "notebook",
-    "orange",
-    "penguin",
-    "rainbow",
-    "sailboat",
-    "treasure",
-    "umbrella",
-    "volcano",
-    "waterfall",
-    "xylophone",
-    "yacht",
-    "zeppelin",
-];
+use std::collections::HashSet;
+use zeroize::Zeroizing;
+
+use crate::encoding;
+use crate::error::{Error, Result};
+use crate::hashing::{hash_password, HashAlgorithm, HashParams};
+use crate::secret::SecretString;
+use crate::strength::{estimate_strength, StrengthEstimate};
+use crate::wordlist::Wordlist;
 
 #[derive(Debug, Clone, Copy)]
 pub enum PasswordStrength {
@@ -68,7 +22,7 @@ pub enum PasswordStrength {
 }
 
 impl PasswordStrength {
-    pub fn to_string(&self) -> &'static str {
+    pub fn to_string(self) -> &'static str {
         match self {
             PasswordStrength::VeryWeak => "Very Weak",
             PasswordStrength::Weak => "Weak",
@@ -78,7 +32,16 @@ impl PasswordStrength {
         }
     }
 
-    #[allow(dead_code)]
+    pub fn from_score(score: u8) -> Self {
+        match score {
+            0 => PasswordStrength::VeryWeak,
+            1 => PasswordStrength::Weak,
+            2 => PasswordStrength::Medium,
+            3 => PasswordStrength::Strong,
+            _ => PasswordStrength::VeryStrong,
+        }
+    }
+
     pub fn color(&self) -> &'static str {
         match self {
             PasswordStrength::VeryWeak => "red",
@@ -90,36 +53,8 @@ impl PasswordStrength {
     }
 }
 
-pub fn format_password(password: &str, format: &str) -> Result<String> {
-    match format.to_lowercase().as_str() {
-        "plain" => Ok(password.to_string()),
-        "base64" => Ok(general_purpose::STANDARD.encode(password.as_bytes())),
-        "hex" => Ok(hex::encode(password.as_bytes())),
-        _ => Err(anyhow::anyhow!("Unsupported format: {}", format)),
-    }
-}
-
-#[allow(dead_code)]
-pub fn copy_to_clipboard(text: &str) -> Result<()> {
-    let mut child = Command::new("pbcopy")
-        .arg("-Prefer")
-        .arg("txt")
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn()?;
-
-    if let Some(mut stdin) = child.stdin.take() {
-        use std::io::Write;
-        stdin.write_all(text.as_bytes())?;
-    }
-
-    let status = child.wait_with_output()?;
-    if !status.status.success() {
-        return Err(anyhow::anyhow!("Failed to copy to clipboard"));
-    }
-
-    Ok(())
+pub fn format_password(password: &SecretString, format: &str) -> Result<SecretString> {
+    encoding::encode(password.expose(), format).map(SecretString::new)
 }
 
 pub fn calculate_entropy(password: &str) -> f64 {
@@ -152,61 +87,47 @@ pub fn calculate_entropy(password: &str) -> f64 {
     password.len() as f64 * (charset_size as f64).log2()
 }
 
+/// Rates a password by how many guesses a pattern-aware attacker would need,
+/// rather than by which character classes it uses.
 pub fn check_password_strength(password: &str) -> PasswordStrength {
-    let entropy = calculate_entropy(password);
-    let length = password.len();
-
-    // Check for common patterns
-    let has_uppercase = password.chars().any(|c| c.is_ascii_uppercase());
-    let has_lowercase = password.chars().any(|c| c.is_ascii_lowercase());
-    let has_numbers = password.chars().any(|c| c.is_ascii_digit());
-    let has_special = password.chars().any(|c| !c.is_alphanumeric());
-
-    let complexity_score = [has_uppercase, has_lowercase, has_numbers, has_special]
-        .iter()
-        .filter(|&&x| x)
-        .count();
-
-    match (entropy, length, complexity_score) {
-        (entropy, _, _) if entropy < 20.0 => PasswordStrength::VeryWeak,
-        (entropy, length, score) if entropy < 30.0 || length < 8 || score < 2 => {
-            PasswordStrength::Weak
-        }
-        (entropy, length, score) if entropy < 40.0 || length < 10 || score < 3 => {
-            PasswordStrength::Medium
-        }
-        (entropy, length, score) if entropy < 50.0 || length < 12 || score < 4 => {
-            PasswordStrength::Strong
-        }
-        _ => PasswordStrength::VeryStrong,
-    }
+    PasswordStrength::from_score(estimate_strength(password).score)
 }
 
-pub fn analyze_password(password: &str) -> Vec<(&'static str, bool)> {
-    let mut analysis = Vec::new();
-
-    // Length checks
-    analysis.push(("At least 8 characters", password.len() >= 8));
-    analysis.push(("At least 12 characters", password.len() >= 12));
-    analysis.push(("At least 16 characters", password.len() >= 16));
+/// Runs the fixed checklist against a password. `breach_count` is the result
+/// of an offline breach lookup, when one was made.
+pub fn analyze_password(password: &str, breach_count: Option<u64>) -> Vec<(String, bool)> {
+    analyze_with_estimate(password, &estimate_strength(password), breach_count)
+}
 
-    // Character set checks
-    analysis.push((
-        "Contains uppercase letters",
-        password.chars().any(|c| c.is_ascii_uppercase()),
-    ));
-    analysis.push((
-        "Contains lowercase letters",
-        password.chars().any(|c| c.is_ascii_lowercase()),
-    ));
-    analysis.push((
-        "Contains numbers",
-        password.chars().any(|c| c.is_ascii_digit()),
-    ));
-    analysis.push((
-        "Contains special characters",
-        password.chars().any(|c| !c.is_alphanumeric()),
-    ));
+/// `analyze_password` for a password whose strength was already estimated.
+pub fn analyze_with_estimate(
+    password: &str,
+    estimate: &StrengthEstimate,
+    breach_count: Option<u64>,
+) -> Vec<(String, bool)> {
+    let mut analysis = vec![
+        // Length checks
+        ("At least 8 characters", password.len() >= 8),
+        ("At least 12 characters", password.len() >= 12),
+        ("At least 16 characters", password.len() >= 16),
+        // Character set checks
+        (
+            "Contains uppercase letters",
+            password.chars().any(|c| c.is_ascii_uppercase()),
+        ),
+        (
+            "Contains lowercase letters",
+            password.chars().any(|c| c.is_ascii_lowercase()),
+        ),
+        (
+            "Contains numbers",
+            password.chars().any(|c| c.is_ascii_digit()),
+        ),
+        (
+            "Contains special characters",
+            password.chars().any(|c| !c.is_alphanumeric()),
+        ),
+    ];
 
     // Entropy check
     let entropy = calculate_entropy(password);
@@ -228,20 +149,50 @@ pub fn analyze_password(password: &str) -> Vec<(&'static str, bool)> {
         .any(|seq| password.to_lowercase().contains(seq));
     analysis.push(("No common sequences", !has_common_sequence));
 
+    // Pattern checks from the guess estimator
+    analysis.push((
+        "No dictionary words or predictable patterns",
+        estimate.is_patternless(),
+    ));
+    analysis.push((
+        "Withstands a fast offline attack for a year",
+        estimate.crack_times[3].seconds >= 365.0 * 24.0 * 3600.0,
+    ));
+
+    let mut analysis: Vec<(String, bool)> = analysis
+        .into_iter()
+        .map(|(criterion, status)| (criterion.to_string(), status))
+        .collect();
+
+    // Breach check
+    match breach_count {
+        Some(0) => analysis.push(("Not found in known data breaches".to_string(), true)),
+        Some(count) => analysis.push((
+            format!("Not found in known data breaches (seen {} times)", count),
+            false,
+        )),
+        None => {}
+    }
+
     analysis
 }
 
+/// Characters one of which `generate_passphrase` may insert.
+const PASSPHRASE_SPECIAL_CHARS: &str = "!@#$%^&*";
+
 pub fn generate_passphrase(
+    wordlist: &Wordlist,
     words: usize,
     separator: &str,
     include_numbers: bool,
     include_special: bool,
-) -> Result<String> {
+) -> Result<SecretString> {
     let mut rng = rand::thread_rng();
-    let mut passphrase_parts = Vec::new();
+    let mut passphrase_parts = Zeroizing::new(Vec::with_capacity(words + 2));
 
+    let list = wordlist.words();
     for _ in 0..words {
-        let word = COMMON_WORDS[rng.gen_range(0..COMMON_WORDS.len())];
+        let word = &list[rng.gen_range(0..list.len())];
         passphrase_parts.push(word.to_string());
     }
 
@@ -252,7 +203,7 @@ pub fn generate_passphrase(
     }
 
     if include_special {
-        let special_chars = "!@#$%^&*";
+        let special_chars = PASSPHRASE_SPECIAL_CHARS;
         let special_char = special_chars
             .chars()
             .nth(rng.gen_range(0..special_chars.len()))
@@ -261,10 +212,117 @@ pub fn generate_passphrase(
         passphrase_parts.insert(special_position, special_char.to_string());
     }
 
-    Ok(passphrase_parts.join(separator))
+    Ok(SecretString::new(passphrase_parts.join(separator)))
+}
+
+/// Entropy in bits of a passphrase from `generate_passphrase`: the words,
+/// plus the inserted number and special character and where they landed.
+pub fn passphrase_entropy(
+    wordlist: &Wordlist,
+    words: usize,
+    include_numbers: bool,
+    include_special: bool,
+) -> f64 {
+    let mut bits = words as f64 * wordlist.bits_per_word();
+    let mut parts = words;
+    if include_numbers {
+        bits += 900f64.log2() + ((parts + 1) as f64).log2();
+        parts += 1;
+    }
+    if include_special {
+        bits += (PASSPHRASE_SPECIAL_CHARS.len() as f64).log2() + ((parts + 1) as f64).log2();
+    }
+    bits
+}
+
+/// Generates `count` distinct passphrases with the same options as
+/// `generate_passphrase`.
+pub fn generate_passphrases(
+    wordlist: &Wordlist,
+    count: usize,
+    words: usize,
+    separator: &str,
+    include_numbers: bool,
+    include_special: bool,
+) -> Result<Vec<SecretString>> {
+    let ln_space = words as f64 * (wordlist.words().len() as f64).ln();
+    if count > 0 && (count as f64).ln() > ln_space + 1e-9 {
+        return Err(Error::Passphrase(format!(
+            "Only {:.0} distinct {}-word passphrases exist; cannot generate {} unique ones",
+            ln_space.exp(),
+            words,
+            count
+        )));
+    }
+
+    // An empty separator can make different word sequences collide, so
+    // give up eventually rather than looping forever.
+    let max_attempts = count.saturating_mul(100).max(1000);
+    let mut seen = HashSet::with_capacity(count);
+    let mut passphrases = Vec::with_capacity(count);
+    for _ in 0..max_attempts {
+        if passphrases.len() == count {
+            break;
+        }
+        let passphrase =
+            generate_passphrase(wordlist, words, separator, include_numbers, include_special)?;
+        if seen.insert(passphrase.clone()) {
+            passphrases.push(passphrase);
+        }
+    }
+    if passphrases.len() < count {
+        return Err(Error::Passphrase(format!(
+            "Could not generate {} unique passphrases; add words or a separator",
+            count
+        )));
+    }
+
+    Ok(passphrases)
+}
+
+/// Largest token `generate_token` will produce, in bytes.
+pub const MAX_TOKEN_BYTES: usize = 1024;
+
+/// Draws `bytes` random bytes from the OS and encodes them with one of
+/// `encoding::BYTE_ENCODINGS`, for API keys, session secrets and the like.
+pub fn generate_token(bytes: usize, encoding: &str) -> Result<SecretString> {
+    if bytes == 0 || bytes > MAX_TOKEN_BYTES {
+        return Err(Error::Token(format!(
+            "Token size must be between 1 and {} bytes, got {}",
+            MAX_TOKEN_BYTES, bytes
+        )));
+    }
+
+    let mut raw = Zeroizing::new(vec![0u8; bytes]);
+    OsRng.fill_bytes(&mut raw);
+    encoding::encode_bytes(&raw, encoding).map(SecretString::new)
+}
+
+/// Strength in bits of a token from `generate_token`: every byte is
+/// uniformly random, whatever the encoding.
+pub fn token_entropy(bytes: usize) -> f64 {
+    bytes as f64 * 8.0
 }
 
-pub fn generate_hash(input: &str, algorithm: &str) -> Result<String> {
+/// Hashes `input` with a plain digest (sha256, sha512), a salted password
+/// hashing algorithm (see `HashAlgorithm`), or encodes it as base64.
+/// `params` only applies to the password hashing algorithms.
+pub fn generate_hash(input: &str, algorithm: &str, params: &HashParams) -> Result<String> {
+    if let Some(kdf) = HashAlgorithm::from_name(algorithm) {
+        return hash_password(input, kdf, params);
+    }
+
+    let has_params = params.memory_kib.is_some()
+        || params.iterations.is_some()
+        || params.parallelism.is_some()
+        || params.cost.is_some();
+    if has_params {
+        return Err(Error::Hash(format!(
+            "{} has no cost parameters; they only apply to password hashing algorithms",
+            algorithm
+        )));
+    }
+
     match algorithm.to_lowercase().as_str() {
         "sha256" => {
             let mut hasher = Sha256::new();
@@ -277,6 +335,14 @@ pub fn generate_hash(input: &str, algorithm: &str) -> Result<String> {
             Ok(hex::encode(hasher.finalize()))
         }
         "base64" => Ok(general_purpose::STANDARD.encode(input.as_bytes())),
-        _ => Err(anyhow::anyhow!("Unsupported hash algorithm: {}", algorithm)),
+        _ => Err(Error::Hash(format!(
+            "Unsupported hash algorithm: {} (valid: sha256, sha512, base64, {})",
+            algorithm,
+            HashAlgorithm::ALL
+                .iter()
+                .map(|kdf| kdf.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
     }
 }
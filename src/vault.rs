@@ -0,0 +1,353 @@
+use anyhow::Result;
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose, Engine as _};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use passgen::secret::SecretString;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
+
+const VAULT_FORMAT: &str = "passgen-vault";
+const VAULT_VERSION: u32 = 2;
+
+/// Version 1 vaults only bound this constant as associated data, leaving
+/// the header unauthenticated. They are still read, and rewritten as
+/// version 2 on the next save.
+const VAULT_V1_AAD: &[u8] = b"passgen-vault-v1";
+
+/// Argon2id cost for new vaults. Existing vaults keep the parameters they
+/// were created with, which are stored in the file header.
+const DEFAULT_MEMORY_KIB: u32 = 64 * 1024;
+const DEFAULT_ITERATIONS: u32 = 3;
+const DEFAULT_PARALLELISM: u32 = 1;
+
+/// A stored credential.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    #[serde(default)]
+    pub username: String,
+    pub password: SecretString,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+impl Entry {
+    pub fn new(name: &str, password: SecretString) -> Self {
+        let now = unix_time();
+        Self {
+            name: name.to_string(),
+            password,
+            created_at: now,
+            updated_at: now,
+            ..Default::default()
+        }
+    }
+
+    /// Marks the entry as modified now.
+    pub fn touch(&mut self) {
+        self.updated_at = unix_time();
+    }
+}
+
+/// Everything but the password, so entries can be logged safely.
+impl fmt::Debug for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Entry")
+            .field("name", &self.name)
+            .field("username", &self.username)
+            .field("password", &"********")
+            .field("url", &self.url)
+            .field("notes", &self.notes)
+            .field("folder", &self.folder)
+            .field("tags", &self.tags)
+            .field("created_at", &self.created_at)
+            .field("updated_at", &self.updated_at)
+            .finish()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct KdfHeader {
+    algorithm: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    salt: String,
+}
+
+/// On-disk layout: a plaintext header describing how to derive the key,
+/// followed by the encrypted JSON list of entries.
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    format: String,
+    version: u32,
+    kdf: KdfHeader,
+    cipher: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl VaultFile {
+    /// Associated data for the ciphertext: the serialized header, so that
+    /// tampering with the format, KDF parameters, salt or cipher fails
+    /// decryption instead of going unnoticed.
+    fn aad(&self) -> Result<Vec<u8>> {
+        if self.version == 1 {
+            return Ok(VAULT_V1_AAD.to_vec());
+        }
+        Ok(serde_json::to_vec(&(
+            &self.format,
+            self.version,
+            &self.kdf,
+            &self.cipher,
+        ))?)
+    }
+}
+
+/// An unlocked vault. The derived key is held so changes can be re-encrypted
+/// on `save` without asking for the master password again.
+pub struct Vault {
+    path: PathBuf,
+    kdf: KdfHeader,
+    key: Zeroizing<[u8; 32]>,
+    entries: Vec<Entry>,
+}
+
+impl Vault {
+    /// Creates a new empty vault at `path`, refusing to overwrite an
+    /// existing file.
+    pub fn create(path: &Path, master: &str) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Claim the path atomically; `save` then replaces the empty file.
+        open_private(path, true).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::AlreadyExists {
+                anyhow::anyhow!("Vault already exists at {}", path.display())
+            } else {
+                anyhow::anyhow!("Failed to create vault {}: {}", path.display(), e)
+            }
+        })?;
+
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let kdf = KdfHeader {
+            algorithm: "argon2id".to_string(),
+            memory_kib: DEFAULT_MEMORY_KIB,
+            iterations: DEFAULT_ITERATIONS,
+            parallelism: DEFAULT_PARALLELISM,
+            salt: general_purpose::STANDARD.encode(salt),
+        };
+        let vault = derive_key(master, &kdf).and_then(|key| {
+            let vault = Self {
+                path: path.to_path_buf(),
+                kdf,
+                key,
+                entries: Vec::new(),
+            };
+            vault.save()?;
+            Ok(vault)
+        });
+        if vault.is_err() {
+            let _ = fs::remove_file(path);
+        }
+        vault
+    }
+
+    /// Opens and decrypts the vault at `path`.
+    pub fn open(path: &Path, master: &str) -> Result<Self> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to read vault {}: {} (run `passgen vault init` first)",
+                path.display(),
+                e
+            )
+        })?;
+        let file: VaultFile = serde_json::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("Malformed vault file: {}", e))?;
+
+        if file.format != VAULT_FORMAT || !(1..=VAULT_VERSION).contains(&file.version) {
+            return Err(anyhow::anyhow!(
+                "Unsupported vault format: {} v{}",
+                file.format,
+                file.version
+            ));
+        }
+        if file.cipher != "xchacha20poly1305" {
+            return Err(anyhow::anyhow!("Unsupported vault cipher: {}", file.cipher));
+        }
+
+        let key = derive_key(master, &file.kdf)?;
+        let nonce = general_purpose::STANDARD.decode(&file.nonce)?;
+        if nonce.len() != 24 {
+            return Err(anyhow::anyhow!("Malformed vault nonce"));
+        }
+        let ciphertext = general_purpose::STANDARD.decode(&file.ciphertext)?;
+        let plaintext = Zeroizing::new(
+            XChaCha20Poly1305::new(&(*key).into())
+                .decrypt(
+                    XNonce::from_slice(&nonce),
+                    Payload {
+                        msg: &ciphertext,
+                        aad: &file.aad()?,
+                    },
+                )
+                .map_err(|_| anyhow::anyhow!("Wrong master password or corrupted vault"))?,
+        );
+        let entries: Vec<Entry> = serde_json::from_slice(&plaintext)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            kdf: file.kdf,
+            key,
+            entries,
+        })
+    }
+
+    /// Encrypts the vault with a fresh nonce and atomically replaces the
+    /// file on disk.
+    pub fn save(&self) -> Result<()> {
+        let plaintext = Zeroizing::new(serde_json::to_vec(&self.entries)?);
+        let mut nonce = [0u8; 24];
+        OsRng.fill_bytes(&mut nonce);
+
+        let mut file = VaultFile {
+            format: VAULT_FORMAT.to_string(),
+            version: VAULT_VERSION,
+            kdf: self.kdf.clone(),
+            cipher: "xchacha20poly1305".to_string(),
+            nonce: general_purpose::STANDARD.encode(nonce),
+            ciphertext: String::new(),
+        };
+        let ciphertext = XChaCha20Poly1305::new(&(*self.key).into())
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &file.aad()?,
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt vault"))?;
+        file.ciphertext = general_purpose::STANDARD.encode(ciphertext);
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp_path = self.path.with_extension("tmp");
+        write_private(&tmp_path, serde_json::to_string_pretty(&file)?.as_bytes())?;
+        fs::rename(&tmp_path, &self.path)?;
+
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Entry> {
+        self.entries.iter_mut().find(|entry| entry.name == name)
+    }
+
+    /// Adds an entry, rejecting names that are already taken.
+    pub fn add(&mut self, entry: Entry) -> Result<()> {
+        if self.get(&entry.name).is_some() {
+            return Err(anyhow::anyhow!("Entry already exists: {}", entry.name));
+        }
+        self.entries.push(entry);
+        self.entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<Entry> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.name == name)
+            .ok_or_else(|| anyhow::anyhow!("No such entry: {}", name))?;
+        Ok(self.entries.remove(index))
+    }
+}
+
+/// Returns `$PASSGEN_VAULT`, or `vault.json` under the XDG data directory.
+pub fn default_path() -> PathBuf {
+    if let Ok(path) = std::env::var("PASSGEN_VAULT") {
+        return PathBuf::from(path);
+    }
+    let data_dir = match std::env::var("XDG_DATA_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()))
+            .join(".local")
+            .join("share"),
+    };
+    data_dir.join("passgen").join("vault.json")
+}
+
+fn derive_key(master: &str, kdf: &KdfHeader) -> Result<Zeroizing<[u8; 32]>> {
+    if kdf.algorithm != "argon2id" {
+        return Err(anyhow::anyhow!(
+            "Unsupported vault key derivation: {}",
+            kdf.algorithm
+        ));
+    }
+
+    let salt = general_purpose::STANDARD.decode(&kdf.salt)?;
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32))
+        .map_err(|e| anyhow::anyhow!("Invalid vault key derivation parameters: {}", e))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(master.as_bytes(), &salt, key.as_mut())
+        .map_err(|e| anyhow::anyhow!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+/// Opens a file for writing that only the current user can read, failing if
+/// it already exists when `create_new` is set.
+fn open_private(path: &Path, create_new: bool) -> std::io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true);
+    if create_new {
+        options.create_new(true);
+    } else {
+        options.create(true).truncate(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+/// Writes a file readable only by the current user.
+fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    use std::io::Write;
+    let mut file = open_private(path, false)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
@@ -1,12 +1,36 @@
 use actix_files::Files;
+use actix_web::http::StatusCode;
 use actix_web::{web, App, HttpResponse, HttpServer, Result};
 use serde::{Deserialize, Serialize};
 
-mod generator;
-mod utils;
+mod config;
 
-use generator::PasswordGenerator;
-use utils::*;
+use config::{Config, Profile};
+use passgen::breach;
+use passgen::generator::{CharClass, CharsetPreset, PasswordGenerator};
+use passgen::hashing::{verify_password, HashParams};
+use passgen::pattern::Pattern;
+use passgen::policy::Policy;
+use passgen::regex::Regex;
+use passgen::report::{
+    CheckResponse, GenerateResponse, HashResponse, PassphraseResponse, TokenResponse,
+    VerifyResponse,
+};
+use passgen::rules::PasswordRules;
+use passgen::secret::SecretString;
+use passgen::utils::*;
+use passgen::wordlist::Wordlist;
+
+/// Largest `count` a single API request may ask for.
+const MAX_BATCH_COUNT: usize = 1000;
+
+/// Longest password `/api/generate` builds; the class-policy sampler's setup
+/// cost grows with the square of the length.
+const MAX_PASSWORD_LENGTH: usize = 1024;
+
+/// Longest password `/api/check` analyses, per OWASP's suggested maximum;
+/// the strength estimate's cost grows quickly with length.
+const MAX_CHECK_LENGTH: usize = 128;
 
 #[derive(Deserialize)]
 struct GenerateRequest {
@@ -15,9 +39,70 @@ struct GenerateRequest {
     lowercase: Option<bool>,
     numbers: Option<bool>,
     special: Option<bool>,
+    charset: Option<Vec<String>>,
+    include_chars: Option<String>,
     exclude_similar: Option<bool>,
     exclude_ambiguous: Option<bool>,
+    require_each: Option<bool>,
+    min_uppercase: Option<usize>,
+    min_lowercase: Option<usize>,
+    min_numbers: Option<usize>,
+    min_special: Option<usize>,
+    max_uppercase: Option<usize>,
+    max_lowercase: Option<usize>,
+    max_numbers: Option<usize>,
+    max_special: Option<usize>,
+    pronounceable: Option<bool>,
+    digits: Option<usize>,
+    symbols: Option<usize>,
+    pattern: Option<String>,
+    regex: Option<String>,
+    rules: Option<String>,
     format: Option<String>,
+    count: Option<usize>,
+    profile: Option<String>,
+}
+
+impl GenerateRequest {
+    /// Fills in whatever the request leaves unset from `profile`. The
+    /// profile's character sets only apply if the request selects none.
+    fn apply_profile(&mut self, profile: &Profile) {
+        let selects_charset = [self.uppercase, self.lowercase, self.numbers, self.special]
+            .contains(&Some(true))
+            || self.charset.as_ref().is_some_and(|names| !names.is_empty())
+            || self.include_chars.is_some();
+        if !selects_charset {
+            self.uppercase = profile.uppercase;
+            self.lowercase = profile.lowercase;
+            self.numbers = profile.numbers;
+            self.special = profile.special;
+            self.charset.clone_from(&profile.charset);
+            self.include_chars.clone_from(&profile.include_chars);
+        }
+
+        self.length = self.length.or(profile.length);
+        self.exclude_similar = self.exclude_similar.or(profile.exclude_similar);
+        self.exclude_ambiguous = self.exclude_ambiguous.or(profile.exclude_ambiguous);
+        self.require_each = self.require_each.or(profile.require_each);
+        self.min_uppercase = self.min_uppercase.or(profile.min_uppercase);
+        self.min_lowercase = self.min_lowercase.or(profile.min_lowercase);
+        self.min_numbers = self.min_numbers.or(profile.min_numbers);
+        self.min_special = self.min_special.or(profile.min_special);
+        self.max_uppercase = self.max_uppercase.or(profile.max_uppercase);
+        self.max_lowercase = self.max_lowercase.or(profile.max_lowercase);
+        self.max_numbers = self.max_numbers.or(profile.max_numbers);
+        self.max_special = self.max_special.or(profile.max_special);
+        if !self.pronounceable.unwrap_or(false)
+            && self.pattern.is_none()
+            && self.regex.is_none()
+            && self.rules.is_none()
+        {
+            self.rules.clone_from(&profile.rules);
+        }
+        if self.format.is_none() {
+            self.format.clone_from(&profile.format);
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -26,53 +111,52 @@ struct PassphraseRequest {
     separator: Option<String>,
     numbers: Option<bool>,
     special: Option<bool>,
+    wordlist: Option<String>,
+    count: Option<usize>,
+    profile: Option<String>,
 }
 
 #[derive(Deserialize)]
-struct CheckRequest {
-    password: String,
-}
-
-#[derive(Deserialize)]
-struct HashRequest {
-    input: String,
-    algorithm: Option<String>,
-}
-
-#[derive(Serialize)]
-struct GenerateResponse {
-    password: String,
-    length: usize,
-    entropy: f64,
-    formatted_password: String,
+struct TokenRequest {
+    bytes: Option<usize>,
+    encoding: Option<String>,
 }
 
-#[derive(Serialize)]
-struct PassphraseResponse {
-    passphrase: String,
-    words: usize,
-    length: usize,
+impl PassphraseRequest {
+    /// Fills in whatever the request leaves unset from `profile`.
+    fn apply_profile(&mut self, profile: &Profile) {
+        self.words = self.words.or(profile.words);
+        if self.separator.is_none() {
+            self.separator.clone_from(&profile.separator);
+        }
+        self.numbers = self.numbers.or(profile.numbers);
+        self.special = self.special.or(profile.special);
+        if self.wordlist.is_none() {
+            self.wordlist.clone_from(&profile.wordlist);
+        }
+    }
 }
 
-#[derive(Serialize)]
-struct CheckResponse {
-    password: String,
-    length: usize,
-    entropy: f64,
-    strength: String,
-    analysis: Vec<AnalysisItem>,
+#[derive(Deserialize)]
+struct CheckRequest {
+    password: SecretString,
+    rules: Option<String>,
+    policy: Option<Vec<String>>,
 }
 
-#[derive(Serialize)]
-struct AnalysisItem {
-    criterion: String,
-    status: bool,
+#[derive(Deserialize)]
+struct HashRequest {
+    input: SecretString,
+    algorithm: Option<String>,
+    memory_kib: Option<u32>,
+    iterations: Option<u32>,
+    parallelism: Option<u32>,
+    cost: Option<u32>,
 }
 
-#[derive(Serialize)]
-struct HashResponse {
-    input: String,
-    algorithm: String,
+#[derive(Deserialize)]
+struct VerifyRequest {
+    password: SecretString,
     hash: String,
 }
 
@@ -81,9 +165,24 @@ struct ErrorResponse {
     error: String,
 }
 
-async fn generate_password(req: web::Json<GenerateRequest>) -> Result<HttpResponse> {
+async fn generate_password(
+    mut req: web::Json<GenerateRequest>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse> {
+    if let Some(name) = req.profile.clone() {
+        match config.profile(&name) {
+            Ok(profile) => req.apply_profile(profile),
+            Err(e) => {
+                let error = ErrorResponse {
+                    error: format!("Profile error: {}", e),
+                };
+                return Ok(HttpResponse::BadRequest().json(error));
+            }
+        }
+    }
+
     let mut generator = PasswordGenerator::new();
-    let length = req.length.unwrap_or(16);
+    let mut length = req.length.unwrap_or(16);
 
     // Set character sets based on flags
     if req.uppercase.unwrap_or(false) {
@@ -98,6 +197,22 @@ async fn generate_password(req: web::Json<GenerateRequest>) -> Result<HttpRespon
     if req.special.unwrap_or(false) {
         generator.include_special();
     }
+    for name in req.charset.iter().flatten() {
+        match CharsetPreset::from_name(name) {
+            Ok(preset) => {
+                generator.with_charset(preset);
+            }
+            Err(e) => {
+                let error = ErrorResponse {
+                    error: format!("Charset error: {}", e),
+                };
+                return Ok(HttpResponse::BadRequest().json(error));
+            }
+        }
+    }
+    if let Some(chars) = &req.include_chars {
+        generator.include_chars(chars);
+    }
     if req.exclude_similar.unwrap_or(false) {
         generator.exclude_similar();
     }
@@ -109,54 +224,203 @@ async fn generate_password(req: web::Json<GenerateRequest>) -> Result<HttpRespon
     if !(req.uppercase.unwrap_or(false)
         || req.lowercase.unwrap_or(false)
         || req.numbers.unwrap_or(false)
-        || req.special.unwrap_or(false))
+        || req.special.unwrap_or(false)
+        || req.charset.as_ref().is_some_and(|names| !names.is_empty())
+        || req.include_chars.is_some())
     {
         generator.include_all();
     }
 
-    match generator.generate(length) {
-        Ok(password) => {
-            let format = req.format.as_deref().unwrap_or("plain");
-            match format_password(&password, format) {
-                Ok(formatted_password) => {
-                    let response = GenerateResponse {
-                        password: password.clone(),
-                        length: password.len(),
-                        entropy: calculate_entropy(&password),
-                        formatted_password,
-                    };
-                    Ok(HttpResponse::Ok().json(response))
-                }
-                Err(e) => {
-                    let error = ErrorResponse {
-                        error: format!("Format error: {}", e),
-                    };
-                    Ok(HttpResponse::BadRequest().json(error))
+    // Apply per-class policy limits
+    if req.require_each.unwrap_or(false) {
+        generator.require_each_class();
+    }
+    let limits = [
+        (CharClass::Uppercase, req.min_uppercase, req.max_uppercase),
+        (CharClass::Lowercase, req.min_lowercase, req.max_lowercase),
+        (CharClass::Number, req.min_numbers, req.max_numbers),
+        (CharClass::Special, req.min_special, req.max_special),
+    ];
+    for (class, min, max) in limits {
+        if let Some(min) = min {
+            generator.min_count(class, min);
+        }
+        if let Some(max) = max {
+            generator.max_count(class, max);
+        }
+    }
+
+    if req.pronounceable.unwrap_or(false) {
+        generator.pronounceable(req.digits.unwrap_or(0), req.symbols.unwrap_or(0));
+    }
+    if let Some(pattern) = &req.pattern {
+        match Pattern::parse(pattern) {
+            Ok(pattern) => {
+                generator.pattern(pattern);
+            }
+            Err(e) => {
+                let error = ErrorResponse {
+                    error: format!("Pattern error: {}", e),
+                };
+                return Ok(HttpResponse::BadRequest().json(error));
+            }
+        }
+    }
+    if let Some(regex) = &req.regex {
+        match Regex::parse(regex) {
+            Ok(regex) => {
+                generator.regex(regex);
+            }
+            Err(e) => {
+                let error = ErrorResponse {
+                    error: format!("Regex error: {}", e),
+                };
+                return Ok(HttpResponse::BadRequest().json(error));
+            }
+        }
+    }
+    if let Some(rules) = &req.rules {
+        match PasswordRules::parse(rules) {
+            Ok(rules) => {
+                if req.length.is_none() {
+                    length = rules.default_length(length);
                 }
+                generator.rules(rules);
+            }
+            Err(e) => {
+                let error = ErrorResponse {
+                    error: format!("Rules error: {}", e),
+                };
+                return Ok(HttpResponse::BadRequest().json(error));
             }
         }
-        Err(e) => {
-            let error = ErrorResponse {
-                error: format!("Generation error: {}", e),
-            };
-            Ok(HttpResponse::InternalServerError().json(error))
+    }
+    if length > MAX_PASSWORD_LENGTH {
+        let error = ErrorResponse {
+            error: format!(
+                "Length error: at most {} characters per request",
+                MAX_PASSWORD_LENGTH
+            ),
+        };
+        return Ok(HttpResponse::BadRequest().json(error));
+    }
+    let count = req.count;
+    if count.is_some_and(|count| count > MAX_BATCH_COUNT) {
+        let error = ErrorResponse {
+            error: format!("Count error: at most {} per request", MAX_BATCH_COUNT),
+        };
+        return Ok(HttpResponse::BadRequest().json(error));
+    }
+    let format = req.format.clone().unwrap_or_else(|| "plain".to_string());
+
+    // Policy setup and sampling can be slow, so keep them off the async
+    // workers
+    let generated = web::block(move || {
+        let generation_error =
+            |status, e: passgen::Error| (status, format!("Generation error: {}", e));
+        let entropy = generator
+            .entropy(length)
+            .map_err(|e| generation_error(StatusCode::BAD_REQUEST, e))?;
+
+        // With `count`, respond with a list of distinct passwords
+        if let Some(count) = count {
+            return generator
+                .batch(length, count)
+                .and_then(|batch| {
+                    batch
+                        .map(|password| {
+                            let password = password?;
+                            let formatted_password = format_password(&password, &format)?;
+                            Ok(GenerateResponse::new(password, formatted_password, entropy))
+                        })
+                        .collect::<passgen::Result<Vec<_>>>()
+                })
+                .map_err(|e| generation_error(StatusCode::BAD_REQUEST, e));
         }
+
+        let password = generator
+            .generate(length)
+            .map_err(|e| generation_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        let formatted_password = format_password(&password, &format)
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("Format error: {}", e)))?;
+        Ok(vec![GenerateResponse::new(
+            password,
+            formatted_password,
+            entropy,
+        )])
+    })
+    .await?;
+
+    match generated {
+        Ok(responses) if count.is_some() => Ok(HttpResponse::Ok().json(responses)),
+        Ok(mut responses) => Ok(HttpResponse::Ok().json(responses.remove(0))),
+        Err((status, error)) => Ok(HttpResponse::build(status).json(ErrorResponse { error })),
     }
 }
 
-async fn generate_passphrase_handler(req: web::Json<PassphraseRequest>) -> Result<HttpResponse> {
+async fn generate_passphrase_handler(
+    mut req: web::Json<PassphraseRequest>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse> {
+    if let Some(name) = req.profile.clone() {
+        match config.profile(&name) {
+            Ok(profile) => req.apply_profile(profile),
+            Err(e) => {
+                let error = ErrorResponse {
+                    error: format!("Profile error: {}", e),
+                };
+                return Ok(HttpResponse::BadRequest().json(error));
+            }
+        }
+    }
+
     let words = req.words.unwrap_or(4);
     let separator = req.separator.as_deref().unwrap_or(" ");
     let numbers = req.numbers.unwrap_or(false);
     let special = req.special.unwrap_or(false);
 
-    match generate_passphrase(words, separator, numbers, special) {
-        Ok(passphrase) => {
-            let response = PassphraseResponse {
-                passphrase: passphrase.clone(),
-                words,
-                length: passphrase.len(),
+    // Only built-in lists; the server never reads wordlist paths from requests
+    let wordlist = match Wordlist::builtin(req.wordlist.as_deref().unwrap_or("common")) {
+        Ok(wordlist) => wordlist,
+        Err(e) => {
+            let error = ErrorResponse {
+                error: format!("Wordlist error: {}", e),
             };
+            return Ok(HttpResponse::BadRequest().json(error));
+        }
+    };
+    let entropy = passphrase_entropy(&wordlist, words, numbers, special);
+
+    // With `count`, respond with a list of distinct passphrases
+    if let Some(count) = req.count {
+        if count > MAX_BATCH_COUNT {
+            let error = ErrorResponse {
+                error: format!("Count error: at most {} per request", MAX_BATCH_COUNT),
+            };
+            return Ok(HttpResponse::BadRequest().json(error));
+        }
+        return match generate_passphrases(&wordlist, count, words, separator, numbers, special) {
+            Ok(passphrases) => {
+                let responses: Vec<PassphraseResponse> = passphrases
+                    .into_iter()
+                    .map(|passphrase| {
+                        PassphraseResponse::new(passphrase, words, &wordlist, entropy)
+                    })
+                    .collect();
+                Ok(HttpResponse::Ok().json(responses))
+            }
+            Err(e) => {
+                let error = ErrorResponse {
+                    error: format!("Passphrase generation error: {}", e),
+                };
+                Ok(HttpResponse::BadRequest().json(error))
+            }
+        };
+    }
+
+    match generate_passphrase(&wordlist, words, separator, numbers, special) {
+        Ok(passphrase) => {
+            let response = PassphraseResponse::new(passphrase, words, &wordlist, entropy);
             Ok(HttpResponse::Ok().json(response))
         }
         Err(e) => {
@@ -168,38 +432,110 @@ async fn generate_passphrase_handler(req: web::Json<PassphraseRequest>) -> Resul
     }
 }
 
-async fn check_password(req: web::Json<CheckRequest>) -> Result<HttpResponse> {
-    let password = &req.password;
-    let strength = check_password_strength(password);
-    let analysis = analyze_password(password);
+async fn generate_token_handler(req: web::Json<TokenRequest>) -> Result<HttpResponse> {
+    let bytes = req.bytes.unwrap_or(32);
+    let encoding = req.encoding.as_deref().unwrap_or("base64url");
 
-    let analysis_items: Vec<AnalysisItem> = analysis
-        .into_iter()
-        .map(|(criterion, status)| AnalysisItem {
-            criterion: criterion.to_string(),
-            status,
-        })
-        .collect();
+    // Every failure here is a size or encoding the request asked for
+    match generate_token(bytes, encoding) {
+        Ok(token) => Ok(HttpResponse::Ok().json(TokenResponse::new(token, bytes, encoding))),
+        Err(e) => {
+            let error = ErrorResponse {
+                error: format!("Token error: {}", e),
+            };
+            Ok(HttpResponse::BadRequest().json(error))
+        }
+    }
+}
 
-    let response = CheckResponse {
-        password: password.clone(),
-        length: password.len(),
-        entropy: calculate_entropy(password),
-        strength: strength.to_string().to_string(),
-        analysis: analysis_items,
+async fn check_password(req: web::Json<CheckRequest>) -> Result<HttpResponse> {
+    let req = req.into_inner();
+    if req.password.expose().chars().count() > MAX_CHECK_LENGTH {
+        let error = ErrorResponse {
+            error: format!(
+                "Password error: at most {} characters per request",
+                MAX_CHECK_LENGTH
+            ),
+        };
+        return Ok(HttpResponse::BadRequest().json(error));
+    }
+    let rules = match req.rules.as_deref().map(PasswordRules::parse).transpose() {
+        Ok(rules) => rules,
+        Err(e) => {
+            let error = ErrorResponse {
+                error: format!("Rules error: {}", e),
+            };
+            return Ok(HttpResponse::BadRequest().json(error));
+        }
+    };
+    let policies = match req
+        .policy
+        .iter()
+        .flatten()
+        .map(|name| Policy::from_name(name))
+        .collect::<passgen::Result<Vec<_>>>()
+    {
+        Ok(policies) => policies,
+        Err(e) => {
+            let error = ErrorResponse {
+                error: format!("Policy error: {}", e),
+            };
+            return Ok(HttpResponse::BadRequest().json(error));
+        }
     };
 
-    Ok(HttpResponse::Ok().json(response))
+    // The breach lookup and strength estimate are slow, so keep them off
+    // the async workers
+    let password = req.password;
+    let analysis = web::block(move || {
+        let password = password.expose();
+        // Breach lookups only run when the server was given a local dataset
+        let breached = std::env::var_os("PASSGEN_BREACH_DB")
+            .map(|db| breach::breach_count(std::path::Path::new(&db), password))
+            .transpose()?;
+        let mut response = CheckResponse::new(password, breached);
+        // The caller already has the password; don't send it back over the wire
+        response.password = None;
+        if let Some(rules) = &rules {
+            response = response.with_rules(password, rules);
+        }
+        for policy in policies {
+            response = response.with_policy(password, policy);
+        }
+        Ok::<_, passgen::Error>(response)
+    })
+    .await?;
+
+    match analysis {
+        Ok(response) => Ok(HttpResponse::Ok().json(response)),
+        Err(e) => {
+            let error = ErrorResponse {
+                error: format!("Breach lookup error: {}", e),
+            };
+            Ok(HttpResponse::InternalServerError().json(error))
+        }
+    }
 }
 
 async fn generate_hash_handler(req: web::Json<HashRequest>) -> Result<HttpResponse> {
+    let req = req.into_inner();
     let algorithm = req.algorithm.as_deref().unwrap_or("sha256");
+    let params = HashParams {
+        memory_kib: req.memory_kib,
+        iterations: req.iterations,
+        parallelism: req.parallelism,
+        cost: req.cost,
+    };
 
-    match generate_hash(&req.input, algorithm) {
+    // Password hashing is deliberately slow, so keep it off the async workers
+    let input = req.input;
+    let hash_algorithm = algorithm.to_string();
+    let hash = web::block(move || generate_hash(input.expose(), &hash_algorithm, &params)).await?;
+    match hash {
         Ok(hash) => {
             let response = HashResponse {
-                input: req.input.clone(),
                 algorithm: algorithm.to_string(),
+                input: None,
                 hash,
             };
             Ok(HttpResponse::Ok().json(response))
@@ -208,7 +544,23 @@ async fn generate_hash_handler(req: web::Json<HashRequest>) -> Result<HttpRespon
             let error = ErrorResponse {
                 error: format!("Hash generation error: {}", e),
             };
-            Ok(HttpResponse::InternalServerError().json(error))
+            Ok(HttpResponse::BadRequest().json(error))
+        }
+    }
+}
+
+async fn verify_handler(req: web::Json<VerifyRequest>) -> Result<HttpResponse> {
+    // Verifying is deliberately slow, so keep it off the async workers
+    let req = req.into_inner();
+    let verification =
+        web::block(move || verify_password(req.password.expose(), &req.hash)).await?;
+    match verification {
+        Ok(verification) => Ok(HttpResponse::Ok().json(VerifyResponse::from(verification))),
+        Err(e) => {
+            let error = ErrorResponse {
+                error: format!("Verification error: {}", e),
+            };
+            Ok(HttpResponse::BadRequest().json(error))
         }
     }
 }
@@ -803,6 +1155,15 @@ async fn index() -> Result<HttpResponse> {
                         <input type="text" id="separator" class="form-control" value=" " maxlength="5">
                     </div>
                     
+                    <div class="form-group">
+                        <label for="wordlist">Wordlist:</label>
+                        <select id="wordlist" class="form-control">
+                            <option value="common">Common (48 words)</option>
+                            <option value="eff-large">EFF Large (7776 words)</option>
+                            <option value="eff-short">EFF Short (1296 words)</option>
+                        </select>
+                    </div>
+                    
                     <div class="form-group">
                         <label>Options:</label>
                         <div class="checkbox-group">
@@ -848,9 +1209,14 @@ async fn index() -> Result<HttpResponse> {
                     <div class="form-group">
                         <label for="hashAlgorithm">Hash Algorithm:</label>
                         <select id="hashAlgorithm" class="form-control">
-                            <option value="sha256">SHA-256</option>
-                            <option value="sha512">SHA-512</option>
-                            <option value="base64">Base64</option>
+                            <option value="argon2id">Argon2id (password storage)</option>
+                            <option value="bcrypt">bcrypt (password storage)</option>
+                            <option value="scrypt">scrypt (password storage)</option>
+                            <option value="pbkdf2-sha256">PBKDF2-SHA256 (password storage)</option>
+                            <option value="pbkdf2-sha512">PBKDF2-SHA512 (password storage)</option>
+                            <option value="sha256">SHA-256 (unsalted digest)</option>
+                            <option value="sha512">SHA-512 (unsalted digest)</option>
+                            <option value="base64">Base64 (encoding, not a hash)</option>
                         </select>
                     </div>
                     
@@ -953,6 +1319,7 @@ async fn index() -> Result<HttpResponse> {
             const formData = {
                 words: parseInt(document.getElementById('words').value),
                 separator: document.getElementById('separator').value,
+                wordlist: document.getElementById('wordlist').value,
                 numbers: document.getElementById('passphrase_numbers').checked,
                 special: document.getElementById('passphrase_special').checked
             };
@@ -982,6 +1349,10 @@ async fn index() -> Result<HttpResponse> {
                                 <div class="stat-value">${result.length}</div>
                                 <div class="stat-label">Length</div>
                             </div>
+                            <div class="stat">
+                                <div class="stat-value">${result.entropy.toFixed(1)}</div>
+                                <div class="stat-label">Entropy (bits)</div>
+                            </div>
                         </div>
                         <button class="copy-btn" onclick="copyToClipboard('${result.passphrase}')">
                             📋 Copy to Clipboard
@@ -1039,7 +1410,6 @@ async fn index() -> Result<HttpResponse> {
                     
                     resultDiv.innerHTML = `
                         <h3>Password Strength Analysis</h3>
-                        <div class="password-display">${result.password}</div>
                         <div class="stats">
                             <div class="stat">
                                 <div class="stat-value">${result.length}</div>
@@ -1053,6 +1423,10 @@ async fn index() -> Result<HttpResponse> {
                                 <div class="stat-value">${result.strength}</div>
                                 <div class="stat-label">Strength</div>
                             </div>
+                            <div class="stat">
+                                <div class="stat-value">${result.crack_times[3].display}</div>
+                                <div class="stat-label">Offline Crack Time</div>
+                            </div>
                         </div>
                         <div class="strength-meter">
                             <div class="strength-bar">
@@ -1105,10 +1479,6 @@ async fn index() -> Result<HttpResponse> {
                     const resultDiv = document.getElementById('hashResult');
                     resultDiv.innerHTML = `
                         <h3>Generated Hash</h3>
-                        <div class="form-group">
-                            <label>Input:</label>
-                            <div class="password-display">${result.input}</div>
-                        </div>
                         <div class="form-group">
                             <label>Algorithm:</label>
                             <div class="password-display">${result.algorithm.toUpperCase()}</div>
@@ -1195,17 +1565,24 @@ async fn index() -> Result<HttpResponse> {
 async fn main() -> std::io::Result<()> {
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
 
+    let config = Config::load()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+    let config = web::Data::new(config);
+
     log::info!("Starting passgen web server at http://localhost:8080");
 
-    HttpServer::new(|| {
+    HttpServer::new(move || {
         App::new()
+            .app_data(config.clone())
             .service(web::resource("/").to(index))
             .service(web::resource("/api/generate").route(web::post().to(generate_password)))
             .service(
                 web::resource("/api/passphrase").route(web::post().to(generate_passphrase_handler)),
             )
+            .service(web::resource("/api/token").route(web::post().to(generate_token_handler)))
             .service(web::resource("/api/check").route(web::post().to(check_password)))
             .service(web::resource("/api/hash").route(web::post().to(generate_hash_handler)))
+            .service(web::resource("/api/verify").route(web::post().to(verify_handler)))
             .service(Files::new("/static", "static").show_files_listing())
     })
     .bind("127.0.0.1:8080")?
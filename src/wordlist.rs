@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+
+/// The small list passphrases have always used, kept as the default so
+/// existing setups behave the same.
+const COMMON_WORDS: &[&str] = &[
+    "apple",
+    "banana",
+    "cherry",
+    "dragon",
+    "eagle",
+    "forest",
+    "garden",
+    "house",
+    "island",
+    "jungle",
+    "knight",
+    "lemon",
+    "mountain",
+    "ocean",
+    "planet",
+    "queen",
+    "river",
+    "sunset",
+    "tiger",
+    "umbrella",
+    "village",
+    "window",
+    "yellow",
+    "zebra",
+    "anchor",
+    "bridge",
+    "castle",
+    "diamond",
+    "elephant",
+    "flower",
+    "guitar",
+    "hammer",
+    "iceberg",
+    "jacket",
+    "kangaroo",
+    "lighthouse",
+    "moonlight",
+    "notebook",
+    "orange",
+    "penguin",
+    "rainbow",
+    "sailboat",
+    "treasure",
+    "volcano",
+    "waterfall",
+    "xylophone",
+    "yacht",
+    "zeppelin",
+];
+
+/// The EFF diceware lists (CC BY 3.0 US; see src/data/README.md).
+const EFF_LARGE: &str = include_str!("data/eff_large_wordlist.txt");
+const EFF_SHORT: &str = include_str!("data/eff_short_wordlist_1.txt");
+
+/// Names accepted by `Wordlist::builtin`.
+pub const BUILTIN_WORDLISTS: &[&str] = &["common", "eff-large", "eff-short"];
+
+/// A list of distinct words to draw passphrase words from.
+#[derive(Debug, Clone)]
+pub struct Wordlist {
+    name: String,
+    words: Vec<String>,
+    /// For complete diceware lists, the number of dice rolled per word. The
+    /// words are then stored in roll order, so the rolls read as a base-6
+    /// number index the list directly.
+    dice: Option<usize>,
+}
+
+impl Wordlist {
+    pub fn builtin(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "common" => Ok(Self {
+                name: "common".to_string(),
+                words: COMMON_WORDS.iter().map(|word| word.to_string()).collect(),
+                dice: None,
+            }),
+            "eff-large" => Self::parse("eff-large".to_string(), EFF_LARGE),
+            "eff-short" => Self::parse("eff-short".to_string(), EFF_SHORT),
+            _ => Err(Error::Wordlist(format!(
+                "Unknown wordlist: {} (valid: {}, or a path to a wordlist file)",
+                name,
+                BUILTIN_WORDLISTS.join(", ")
+            ))),
+        }
+    }
+
+    /// Resolves a `--wordlist` argument: a built-in name, or else a file.
+    pub fn from_arg(arg: &str) -> Result<Self> {
+        if BUILTIN_WORDLISTS.contains(&arg.to_lowercase().as_str()) {
+            return Self::builtin(arg);
+        }
+        Self::load(Path::new(arg))
+    }
+
+    /// Loads a wordlist file with either one word per line, or diceware
+    /// lines such as `11111 abacus` (the format of the EFF lists). Blank
+    /// lines and `#` comments are ignored; duplicate words are an error since
+    /// they silently lower the entropy of every passphrase.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            Error::Wordlist(format!("Failed to read wordlist {}: {}", path.display(), e))
+        })?;
+        Self::parse(path.display().to_string(), &contents)
+    }
+
+    fn parse(name: String, contents: &str) -> Result<Self> {
+        let lines: Vec<(usize, &str)> = contents
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .collect();
+
+        let is_diceware = lines.first().is_some_and(|(_, line)| {
+            line.split_once(char::is_whitespace)
+                .is_some_and(|(code, _)| is_dice_code(code))
+        });
+
+        let (words, dice) = if is_diceware {
+            parse_diceware(&name, &lines)?
+        } else {
+            (
+                lines.iter().map(|(_, line)| line.to_string()).collect(),
+                None,
+            )
+        };
+
+        let mut first_seen: HashMap<&str, usize> = HashMap::new();
+        let mut duplicates = Vec::new();
+        for (i, word) in words.iter().enumerate() {
+            if let Some(first) = first_seen.insert(word.as_str(), i) {
+                duplicates.push(format!("{} (entries {} and {})", word, first + 1, i + 1));
+            }
+        }
+        if !duplicates.is_empty() {
+            return Err(Error::Wordlist(format!(
+                "Wordlist {} contains {} duplicate word(s): {}",
+                name,
+                duplicates.len(),
+                duplicates
+                    .into_iter()
+                    .take(5)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+        if words.len() < 2 {
+            return Err(Error::Wordlist(format!(
+                "Wordlist {} needs at least two words",
+                name
+            )));
+        }
+
+        Ok(Self { name, words, dice })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// Entropy contributed by each uniformly chosen word.
+    pub fn bits_per_word(&self) -> f64 {
+        (self.words.len() as f64).log2()
+    }
+
+    /// Dice rolled per word: the list's own roll length for complete
+    /// diceware lists, otherwise the fewest dice with at least as many
+    /// outcomes as there are words.
+    pub fn dice_per_word(&self) -> usize {
+        self.dice.unwrap_or_else(|| {
+            let mut dice = 1;
+            let mut outcomes = 6usize;
+            while outcomes < self.words.len() {
+                dice += 1;
+                outcomes = outcomes.saturating_mul(6);
+            }
+            dice
+        })
+    }
+
+    /// Maps one word's worth of physical dice rolls (digits 1-6; spaces and
+    /// commas are ignored) to a word. For lists whose size is not a power of
+    /// six the roll value is reduced modulo the list size, and the few values
+    /// past the last whole multiple return `None`; those rolls must be
+    /// discarded and re-rolled to keep every word equally likely.
+    pub fn word_for_rolls(&self, rolls: &str) -> Result<Option<&str>> {
+        let digits: Vec<u8> = rolls
+            .bytes()
+            .filter(|b| !b.is_ascii_whitespace() && *b != b',')
+            .collect();
+        let expected = self.dice_per_word();
+        if let Some(bad) = digits.iter().find(|b| !(b'1'..=b'6').contains(b)) {
+            return Err(Error::Wordlist(format!(
+                "Invalid roll '{}': each die must be 1-6",
+                *bad as char
+            )));
+        }
+        if digits.len() != expected {
+            return Err(Error::Wordlist(format!(
+                "Expected {} dice, got {}",
+                expected,
+                digits.len()
+            )));
+        }
+
+        let value = digits.iter().fold(0usize, |value, &digit| {
+            value * 6 + usize::from(digit - b'1')
+        });
+        let outcomes = 6usize.pow(expected as u32);
+        let usable = outcomes - outcomes % self.words.len();
+        if value >= usable {
+            return Ok(None);
+        }
+        Ok(Some(&self.words[value % self.words.len()]))
+    }
+}
+
+fn is_dice_code(code: &str) -> bool {
+    !code.is_empty() && code.bytes().all(|b| (b'1'..=b'6').contains(&b))
+}
+
+/// Parses `CODE WORD` lines, returning the words in roll order and, when
+/// every roll of that many dice is present exactly once, the dice count.
+fn parse_diceware(name: &str, lines: &[(usize, &str)]) -> Result<(Vec<String>, Option<usize>)> {
+    let mut entries: Vec<(String, String)> = Vec::with_capacity(lines.len());
+    for (number, line) in lines {
+        let (code, word) = line
+            .split_once(char::is_whitespace)
+            .filter(|(code, _)| is_dice_code(code))
+            .ok_or_else(|| {
+                Error::Wordlist(format!(
+                    "Wordlist {} line {}: expected `ROLLS WORD`, e.g. `11111 abacus`",
+                    name, number
+                ))
+            })?;
+        entries.push((code.to_string(), word.trim().to_string()));
+    }
+
+    let digits = entries[0].0.len();
+    if entries.iter().any(|(code, _)| code.len() != digits) {
+        return Err(Error::Wordlist(format!(
+            "Wordlist {} mixes roll codes of different lengths",
+            name
+        )));
+    }
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+        return Err(Error::Wordlist(format!(
+            "Wordlist {} lists roll {} more than once",
+            name, pair[0].0
+        )));
+    }
+
+    let complete = u32::try_from(digits)
+        .ok()
+        .and_then(|digits| 6usize.checked_pow(digits))
+        .is_some_and(|size| size == entries.len());
+    let words = entries.into_iter().map(|(_, word)| word).collect();
+    Ok((words, complete.then_some(digits)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eff_large_is_a_complete_five_dice_list() {
+        let wordlist = Wordlist::builtin("eff-large").unwrap();
+        assert_eq!(wordlist.words().len(), 7776);
+        assert_eq!(wordlist.dice_per_word(), 5);
+        assert_eq!(wordlist.word_for_rolls("11111").unwrap(), Some("abacus"));
+        assert_eq!(wordlist.word_for_rolls("66666").unwrap(), Some("zoom"));
+    }
+
+    #[test]
+    fn eff_short_is_a_complete_four_dice_list() {
+        let wordlist = Wordlist::builtin("eff-short").unwrap();
+        assert_eq!(wordlist.words().len(), 1296);
+        assert_eq!(wordlist.dice_per_word(), 4);
+        assert_eq!(wordlist.word_for_rolls("1111").unwrap(), Some("acid"));
+        assert_eq!(wordlist.word_for_rolls("6666").unwrap(), Some("zoom"));
+    }
+}